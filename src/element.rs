@@ -1,6 +1,6 @@
 use serde::{Serialize, Deserialize};
 
-use crate::function::Function;
+use crate::{Value, function::Function};
 
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
@@ -13,12 +13,41 @@ pub struct Output {
     value_index: usize,
 }
 
+/// A value that can be driven by several bus drivers like tri-state buffers
+/// or open-drain outputs. Without active drivers the bus takes the value of
+/// its pull resistor, if it has one, and is `Off` otherwise.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Bus {
+    value_index: usize,
+    #[serde(default)]
+    resolution: BusResolution,
+    #[serde(default)]
+    pull: Option<Value>,
+}
+
+/// How the values of several active drivers of a bus are combined.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BusResolution {
+    /// All drivers have to agree, otherwise it is a bus contention.
+    #[default]
+    TriState,
+    /// The bus is `On` only if all drivers are `On`.
+    WiredAnd,
+    /// The bus is `On` if any driver is `On`.
+    WiredOr,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Component {
     input_value_indices: Vec<usize>,
     output_value_indices: Vec<usize>,
     owned_value_indices: Vec<usize>,
+    #[serde(deserialize_with = "crate::function::deserialize_function")]
     function: Function,
+    /// Power-on values of the first owned values, like the stored bit of a
+    /// flip-flop. The remaining owned values start `Off`.
+    #[serde(default)]
+    initial_state: Vec<Value>,
 }
 
 impl Input {
@@ -41,6 +70,49 @@ impl Output {
     }
 }
 
+impl Bus {
+    pub(super) fn new(value_index: usize, resolution: BusResolution, pull: Option<Value>) -> Self {
+        Bus { value_index, resolution, pull }
+    }
+
+    pub fn value_index(&self) -> usize {
+        self.value_index
+    }
+
+    pub fn resolution(&self) -> BusResolution {
+        self.resolution
+    }
+
+    pub fn pull(&self) -> Option<Value> {
+        self.pull
+    }
+
+    pub(super) fn set_resolution(&mut self, resolution: BusResolution, pull: Option<Value>) {
+        self.resolution = resolution;
+        self.pull = pull;
+    }
+
+    /// Combines the values of the active drivers and returns the value of the
+    /// bus and whether the drivers are in contention.
+    pub fn resolve(&self, driven_values: &[Value]) -> (Value, bool) {
+        let Some(&first) = driven_values.first() else {
+            return (self.pull.unwrap_or(Value::Off), false);
+        };
+
+        match self.resolution {
+            BusResolution::TriState => {
+                if driven_values.iter().all(|&value| value == first) {
+                    (first, false)
+                } else {
+                    (Value::Off, true)
+                }
+            },
+            BusResolution::WiredAnd => (driven_values.iter().fold(Value::On, |acc, &x| acc & x), false),
+            BusResolution::WiredOr => (driven_values.iter().fold(Value::Off, |acc, &x| acc | x), false),
+        }
+    }
+}
+
 impl Component {
     pub fn new(function: Function, input_value_indices: Vec<usize>, output_value_indices: Vec<usize>, owned_value_indices: Vec<usize>) -> Self {
         Self {
@@ -48,6 +120,7 @@ impl Component {
             output_value_indices,
             owned_value_indices,
             function,
+            initial_state: vec![],
         }
     }
 
@@ -66,4 +139,26 @@ impl Component {
     pub fn function(&self) -> &Function {
         &self.function
     }
-}
\ No newline at end of file
+
+    pub(crate) fn function_mut(&mut self) -> &mut Function {
+        &mut self.function
+    }
+
+    pub fn initial_state(&self) -> &[Value] {
+        &self.initial_state
+    }
+
+    pub(super) fn set_initial_state(&mut self, initial_state: Vec<Value>) {
+        self.initial_state = initial_state;
+    }
+
+    pub(crate) fn set_input_value_index(&mut self, input: usize, value_index: usize) {
+        self.input_value_indices[input] = value_index;
+    }
+}
+
+impl std::fmt::Display for BusResolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
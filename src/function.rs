@@ -1,8 +1,8 @@
-use std::fmt::Display;
+use std::{cmp::Ordering, fmt::Display, num::NonZeroUsize};
 
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, Deserializer};
 
-use crate::{Value, Circuit, simulator::Simulator};
+use crate::{Value, Circuit, CircuitError, simulator::Simulator, library::SubcircuitReference, memory::RomImage, truth_table::TruthTable, state_machine::{StateMachine, StateMachineError}};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Function {
@@ -14,10 +14,196 @@ pub enum Function {
     Nor,
     Xnor,
     Circuit(Circuit),
+    Subcircuit(SubcircuitReference),
     FlipFlopRS,
-    FlipFlopJK,
-    FlipFlopD,
-    FlipFlopT,
+    /// The plain flip-flops with their data inputs followed by the clock. They
+    /// are triggered on the clock edges selected by `edge`. Circuits saved
+    /// before they had an edge are loaded with the rising edge.
+    FlipFlopJK {
+        #[serde(default)]
+        edge: ClockEdge,
+    },
+    FlipFlopD {
+        #[serde(default)]
+        edge: ClockEdge,
+    },
+    FlipFlopT {
+        #[serde(default)]
+        edge: ClockEdge,
+    },
+    On,
+    Off,
+    /// Drives its output `On` for the first `duty_cycle` ticks of every
+    /// `period` ticks of the simulator time and `Off` for the rest.
+    Clock { period: NonZeroUsize, duty_cycle: usize },
+    /// Forwards one of its `2^select_lines` data inputs. The data inputs come
+    /// first, followed by the select lines with the least significant bit first.
+    Multiplexer { select_lines: usize },
+    /// Forwards its data input to one of its `2^select_lines` outputs while all
+    /// other outputs are `Off`. The data input is followed by the select lines
+    /// with the least significant bit first.
+    Demultiplexer { select_lines: usize },
+    /// Turns on the one of its `2^width` outputs selected by its `width`
+    /// inputs with the least significant bit first.
+    Decoder { width: usize },
+    /// Outputs the index of the active one of its `2^width` inputs with the
+    /// least significant bit first, followed by a valid output that is `On`
+    /// if any input is `On`. Several active inputs get their indices or'ed.
+    Encoder { width: usize },
+    /// Like `Encoder` but outputs the highest index of all active inputs.
+    PriorityEncoder { width: usize },
+    /// Outputs sum and carry of its two inputs.
+    HalfAdder,
+    /// Outputs sum and carry out of its two inputs and the carry in.
+    FullAdder,
+    /// Ripple-carry adder for the `width` bits of A followed by the `width`
+    /// bits of B, each with the least significant bit first, then carry in and
+    /// subtract. While subtract is `On` B and carry in are inverted, so that
+    /// A - B - carry in is calculated and carry out is `On` if no borrow occurred.
+    /// Outputs the `width` bits of the result, carry out and signed overflow.
+    Adder { width: usize },
+    /// Arithmetic logic unit for the `width` bits of A followed by the `width`
+    /// bits of B, each with the least significant bit first, then three opcode
+    /// bits selecting ADD, SUB, AND, OR, XOR, SHL, SHR or CMP, in this order.
+    /// Shifts move A by B bits and CMP subtracts B from A but only outputs the
+    /// flags. Outputs the `width` bits of the result, then zero, carry,
+    /// negative and overflow flags.
+    Alu { width: usize },
+    /// Synchronous up/down counter wrapping around at `modulus`, which must not
+    /// be larger than `2^width`. The `width` load bits with the least significant
+    /// bit first are followed by load, up, enable, reset and clock. On a clock
+    /// edge selected by `edge` while enable is `On` the counter loads or counts
+    /// up or down. Reset clears the counter immediately. Outputs the `width`
+    /// count bits and a carry that is `On` while enabled and at the last count
+    /// in direction.
+    Counter {
+        width: usize,
+        modulus: usize,
+        #[serde(default)]
+        edge: ClockEdge,
+    },
+    /// Serial in, parallel out shift register with serial in and clock inputs.
+    /// On a clock edge selected by `edge` every bit moves to the next higher
+    /// index and the serial in is stored at index 0. Outputs all `length`
+    /// stored bits.
+    ShiftRegisterSipo {
+        length: usize,
+        #[serde(default)]
+        edge: ClockEdge,
+    },
+    /// Parallel in, serial out shift register with `length` parallel inputs
+    /// followed by load, serial in and clock. On a clock edge selected by
+    /// `edge` the parallel inputs are loaded or the bits are shifted as in
+    /// `ShiftRegisterSipo`. Outputs the bit at the highest index.
+    ShiftRegisterPiso {
+        length: usize,
+        #[serde(default)]
+        edge: ClockEdge,
+    },
+    /// Shift register with `length` parallel inputs followed by two mode bits,
+    /// least significant bit first, serial in for shifting right, serial in for
+    /// shifting left and clock. On a clock edge selected by `edge` mode 0
+    /// holds, 1 shifts towards index 0, 2 shifts towards the highest index and
+    /// 3 loads the parallel inputs. Outputs all `length` stored bits.
+    ShiftRegisterUniversal {
+        length: usize,
+        #[serde(default)]
+        edge: ClockEdge,
+    },
+    /// Edge-triggered flip-flop of the given kind with the control pins and
+    /// clock edge selected in `options`. See `FlipFlopOptions` for the input
+    /// layout. Outputs the state and its inverse.
+    FlipFlop { kind: FlipFlopKind, options: FlipFlopOptions },
+    /// `width` D flip-flops sharing their control pins. See `FlipFlopOptions`
+    /// for the input layout. Outputs the `width` stored bits.
+    Register { width: usize, options: FlipFlopOptions },
+    /// Memory of `2^address_width` words of `data_width` bits. The address bits
+    /// are followed by the data in bits, each with the least significant bit
+    /// first, then write enable and clock. On a clock edge selected by `edge`
+    /// while write enable is `On` the data in is stored at the address. Outputs
+    /// the word stored at the address. The words are kept in the owned values.
+    Ram {
+        address_width: usize,
+        data_width: usize,
+        #[serde(default)]
+        edge: ClockEdge,
+    },
+    /// Read only memory of `2^address_width` words of `data_width` bits with
+    /// the contents of `image`. Outputs the word at the address given by the
+    /// inputs with the least significant bit first, or 0 beyond the image.
+    Rom { address_width: usize, data_width: usize, image: RomImage },
+    /// Magnitude comparator for the `width` bits of A followed by the `width`
+    /// bits of B, each with the least significant bit first, then the cascade
+    /// inputs A<B, A=B and A>B. Outputs A<B, A=B and A>B. If A and B are equal
+    /// the outputs follow the cascade inputs as on the 7485, so for a single
+    /// comparator A=B should be `On`. When cascading, only the comparator for
+    /// the most significant bits should be `signed`.
+    Comparator { width: usize, signed: bool },
+    /// Drives its `width` data inputs onto the buses at its outputs while the
+    /// enable input that follows them is `On`. See `Circuit::add_bus_driver`.
+    TriStateBuffer { width: usize },
+    /// Pulls each bus at its outputs `Off` while the corresponding one of its
+    /// `width` inputs is `Off` and releases it otherwise. Usually combined
+    /// with `BusResolution::WiredAnd` and a pull-up.
+    OpenDrain { width: usize },
+    /// Drives each bus at its outputs `On` while the corresponding one of its
+    /// `width` inputs is `On` and releases it otherwise. Usually combined with
+    /// `BusResolution::WiredOr` and a pull-down.
+    OpenSource { width: usize },
+    /// Gated D latch with the inputs data and enable. Follows data while
+    /// enable is `On` and holds its state otherwise. Outputs Q and not Q.
+    LatchD,
+    /// Gated SR latch with the inputs set, reset and enable. While enable is
+    /// `On` it behaves like `FlipFlopRS` and it holds its state otherwise.
+    /// Outputs Q and not Q.
+    LatchSR,
+    /// Combinational block whose outputs are looked up in the table.
+    TruthTable(TruthTable),
+    /// Moore or Mealy machine with its inputs followed by the clock. The index
+    /// of the current state is stored in the owned values.
+    StateMachine(StateMachine),
+}
+
+/// Why a function cannot be used in a circuit, see `Function::validate`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FunctionError {
+    UnresolvedSubcircuit { name: String, version: u32 },
+    InvalidCircuit(Box<CircuitError>),
+    ZeroModulus,
+    ModulusTooLarge { modulus: usize, width: usize },
+    ZeroLength,
+    /// `2^width` lines or memory words do not fit into a `usize`.
+    TooWide { width: usize },
+    InvalidStateMachine(StateMachineError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlipFlopKind {
+    JK,
+    D,
+    T,
+}
+
+/// Optional control pins of `Function::FlipFlop` and `Function::Register`.
+/// The data inputs are followed by enable, then preset and clear, if they are
+/// enabled here, and the clock as last input. Clear takes precedence over
+/// preset and both act immediately, while enable only gates the clock edges.
+/// `edge` selects the clock edges the component is triggered on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlipFlopOptions {
+    pub enable: bool,
+    pub preset_clear: bool,
+    #[serde(default)]
+    pub edge: ClockEdge,
+}
+
+/// The clock edges a flip-flop, register or state machine is triggered on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockEdge {
+    #[default]
+    Rising,
+    Falling,
+    Dual,
 }
 
 impl Function {
@@ -48,19 +234,9 @@ impl Function {
                 let value = !input_values.iter().fold(Value::Off, |acc, &x| acc ^ x);
                 (vec![value], vec![])
             },
-            Function::Circuit(circuit) => {
-                let mut simulator = Simulator::new(circuit.clone());
-
-                for i in 0..input_values.len() {
-                    simulator.set_input(i, input_values[i]);
-                }
-
-                simulator.simulate();
-
-                let values = circuit.all_outputs().iter()
-                    .map(|output| simulator.value_for_index(output.value_index()))
-                    .collect();
-
+            Function::Circuit(circuit) => (evaluate_circuit(circuit, input_values), vec![]),
+            Function::Subcircuit(reference) => {
+                let values = reference.circuit().map_or_else(Vec::new, |circuit| evaluate_circuit(circuit, input_values));
                 (values, vec![])
             },
             Function::FlipFlopRS => {
@@ -72,8 +248,8 @@ impl Function {
                     }
                 }
             },
-            Function::FlipFlopJK => {
-                if is_positiv_transient(owned_values[1], input_values[2]) {
+            Function::FlipFlopJK { edge } => {
+                if edge.is_triggered(owned_values[1], input_values[2]) {
                     let value = match (input_values[0], input_values[1]) {
                         (Value::On, Value::On) => !owned_values[0],
                         (Value::On, Value::Off) => Value::On,
@@ -86,20 +262,258 @@ impl Function {
                     (vec![owned_values[0], !owned_values[0]], vec![owned_values[0], input_values[2]])
                 }
             },
-            Function::FlipFlopD => {
-                if is_positiv_transient(owned_values[1], input_values[1]) {
+            Function::FlipFlopD { edge } => {
+                if edge.is_triggered(owned_values[1], input_values[1]) {
                     (vec![input_values[0], !input_values[0]], vec![input_values[0], input_values[1]])
                 } else {
                     (vec![owned_values[0], !owned_values[0]], vec![owned_values[0], input_values[1]])
                 }
             },
-            Function::FlipFlopT => {
-                if is_positiv_transient(owned_values[1], input_values[1]) && input_values[0] == Value::On {
+            Function::FlipFlopT { edge } => {
+                if edge.is_triggered(owned_values[1], input_values[1]) && input_values[0] == Value::On {
                     (vec![!owned_values[0], owned_values[0]], vec![!owned_values[0], input_values[1]])
                 } else {
                     (vec![owned_values[0], !owned_values[0]], vec![owned_values[0], input_values[1]])
                 }
             },
+            Function::On => (vec![Value::On], vec![]),
+            Function::Off => (vec![Value::Off], vec![]),
+            Function::Clock { .. } => (vec![owned_values[0]], owned_values.to_vec()),
+            Function::Multiplexer { select_lines } => {
+                let data_count = saturating_power_of_two(*select_lines);
+                let selected = to_number(&input_values[data_count..]);
+                (vec![input_values[selected]], vec![])
+            },
+            Function::Demultiplexer { select_lines } => {
+                let selected = to_number(&input_values[1..]);
+                let values = one_hot(selected, saturating_power_of_two(*select_lines)).into_iter()
+                    .map(|value| value & input_values[0])
+                    .collect();
+                (values, vec![])
+            },
+            Function::Decoder { width } => (one_hot(to_number(input_values), saturating_power_of_two(*width)), vec![]),
+            Function::Encoder { width } => {
+                let index = input_values.iter()
+                    .enumerate()
+                    .filter(|(_, &value)| value == Value::On)
+                    .fold(0, |acc, (i, _)| acc | i);
+
+                (encoder_values(index, *width, input_values), vec![])
+            },
+            Function::PriorityEncoder { width } => {
+                let index = input_values.iter().rposition(|&value| value == Value::On).unwrap_or(0);
+                (encoder_values(index, *width, input_values), vec![])
+            },
+            Function::HalfAdder => {
+                let (sum, carry) = full_add(input_values[0], input_values[1], Value::Off);
+                (vec![sum, carry], vec![])
+            },
+            Function::FullAdder => {
+                let (sum, carry) = full_add(input_values[0], input_values[1], input_values[2]);
+                (vec![sum, carry], vec![])
+            },
+            Function::Adder { width } => {
+                let subtract = input_values[2 * width + 1];
+                let a = &input_values[..*width];
+                let b: Vec<_> = input_values[*width..2 * width].iter().map(|&value| value ^ subtract).collect();
+                let carry_in = input_values[2 * width] ^ subtract;
+
+                let (mut values, carry_out, overflow) = ripple_add(a, &b, carry_in);
+                values.push(carry_out);
+                values.push(overflow);
+                (values, vec![])
+            },
+            Function::Alu { width } => (evaluate_alu(*width, input_values), vec![]),
+            Function::Counter { width, modulus, edge } => {
+                let [load, up, enable, reset, clock] = input_values[*width..] else {
+                    panic!("counter expects {} input values", width + 5);
+                };
+
+                let old_count = to_number(&owned_values[..*width]);
+                let count = if reset == Value::On {
+                    0
+                } else if edge.is_triggered(owned_values[*width], clock) && enable == Value::On {
+                    if load == Value::On {
+                        to_number(&input_values[..*width]) % modulus
+                    } else if up == Value::On {
+                        (old_count + 1) % modulus
+                    } else {
+                        (old_count + modulus - 1) % modulus
+                    }
+                } else {
+                    old_count
+                };
+
+                let last_count = if up == Value::On { modulus - 1 } else { 0 };
+                let carry = if count == last_count { enable } else { Value::Off };
+
+                let mut owned_values = to_values(count, *width);
+                owned_values.push(clock);
+
+                let mut values = to_values(count, *width);
+                values.push(carry);
+
+                (values, owned_values)
+            },
+            Function::ShiftRegisterSipo { length, edge } => {
+                let state = &owned_values[..*length];
+                let clock = input_values[1];
+
+                let mut new_state = if edge.is_triggered(owned_values[*length], clock) {
+                    shift_up(state, input_values[0])
+                } else {
+                    state.to_vec()
+                };
+
+                let values = new_state.clone();
+                new_state.push(clock);
+                (values, new_state)
+            },
+            Function::ShiftRegisterPiso { length, edge } => {
+                let state = &owned_values[..*length];
+                let [load, serial_in, clock] = input_values[*length..] else {
+                    panic!("parallel in, serial out shift register expects {} input values", length + 3);
+                };
+
+                let mut new_state = if !edge.is_triggered(owned_values[*length], clock) {
+                    state.to_vec()
+                } else if load == Value::On {
+                    input_values[..*length].to_vec()
+                } else {
+                    shift_up(state, serial_in)
+                };
+
+                let values = vec![new_state[length - 1]];
+                new_state.push(clock);
+                (values, new_state)
+            },
+            Function::ShiftRegisterUniversal { length, edge } => {
+                let state = &owned_values[..*length];
+                let [mode0, mode1, right_serial_in, left_serial_in, clock] = input_values[*length..] else {
+                    panic!("universal shift register expects {} input values", length + 5);
+                };
+
+                let mut new_state = if !edge.is_triggered(owned_values[*length], clock) {
+                    state.to_vec()
+                } else {
+                    match (mode1, mode0) {
+                        (Value::Off, Value::Off) => state.to_vec(),
+                        (Value::Off, Value::On) => shift_down(state, right_serial_in),
+                        (Value::On, Value::Off) => shift_up(state, left_serial_in),
+                        (Value::On, Value::On) => input_values[..*length].to_vec(),
+                    }
+                };
+
+                let values = new_state.clone();
+                new_state.push(clock);
+                (values, new_state)
+            },
+            Function::FlipFlop { kind, options } => {
+                let data_count = kind.data_value_count();
+                let state = owned_values[0];
+
+                let value = options.next_state(&input_values[data_count..], owned_values[1], state, || match kind {
+                    FlipFlopKind::JK => match (input_values[0], input_values[1]) {
+                        (Value::On, Value::On) => !state,
+                        (Value::On, Value::Off) => Value::On,
+                        (Value::Off, Value::On) => Value::Off,
+                        (Value::Off, Value::Off) => state,
+                    },
+                    FlipFlopKind::D => input_values[0],
+                    FlipFlopKind::T => state ^ input_values[0],
+                });
+
+                let clock = input_values[input_values.len() - 1];
+                (vec![value, !value], vec![value, clock])
+            },
+            Function::Register { width, options } => {
+                let clock = input_values[input_values.len() - 1];
+
+                let mut new_state: Vec<_> = (0..*width)
+                    .map(|i| options.next_state(&input_values[*width..], owned_values[*width], owned_values[i], || input_values[i]))
+                    .collect();
+
+                let values = new_state.clone();
+                new_state.push(clock);
+                (values, new_state)
+            },
+            Function::Ram { address_width, data_width, edge } => {
+                let memory_len = saturating_power_of_two(*address_width) * data_width;
+                let address = to_number(&input_values[..*address_width]);
+                let data_in = &input_values[*address_width..address_width + data_width];
+                let write_enable = input_values[address_width + data_width];
+                let clock = input_values[address_width + data_width + 1];
+
+                let word = address * data_width..(address + 1) * data_width;
+                let mut new_owned_values = owned_values.to_vec();
+
+                if edge.is_triggered(owned_values[memory_len], clock) && write_enable == Value::On {
+                    new_owned_values[word.clone()].copy_from_slice(data_in);
+                }
+
+                new_owned_values[memory_len] = clock;
+                (new_owned_values[word].to_vec(), new_owned_values)
+            },
+            Function::Rom { data_width, image, .. } => {
+                let word = image.loaded_words().get(to_number(input_values)).copied().unwrap_or(0);
+                (to_values(word, *data_width), vec![])
+            },
+            Function::Comparator { width, signed } => {
+                let a = to_number(&input_values[..*width]);
+                let b = to_number(&input_values[*width..2 * width]);
+                let [less_in, equal_in, greater_in] = input_values[2 * width..] else {
+                    panic!("comparator expects {} input values", 2 * width + 3);
+                };
+
+                let ordering = if *signed {
+                    to_signed(a, *width).cmp(&to_signed(b, *width))
+                } else {
+                    a.cmp(&b)
+                };
+
+                let values = match ordering {
+                    Ordering::Less => vec![Value::On, Value::Off, Value::Off],
+                    Ordering::Greater => vec![Value::Off, Value::Off, Value::On],
+                    Ordering::Equal => vec![!equal_in & !greater_in, equal_in, !equal_in & !less_in],
+                };
+
+                (values, vec![])
+            },
+            Function::TriStateBuffer { width } => {
+                let enable = input_values[*width];
+                let values = input_values[..*width].iter().map(|&value| value & enable).collect();
+                (values, input_values.to_vec())
+            },
+            Function::OpenDrain { .. } => (input_values.to_vec(), input_values.to_vec()),
+            Function::OpenSource { .. } => (input_values.to_vec(), input_values.to_vec()),
+            Function::LatchD => {
+                let value = if input_values[1] == Value::On { input_values[0] } else { owned_values[0] };
+                (vec![value, !value], vec![value])
+            },
+            Function::LatchSR => {
+                match (input_values[0], input_values[1], input_values[2]) {
+                    (Value::On, Value::On, Value::On) => (vec![Value::Off, Value::Off], owned_values.to_vec()),
+                    (set, reset, Value::On) if set != reset => (vec![set, !set], vec![set]),
+                    _ => (vec![owned_values[0], !owned_values[0]], owned_values.to_vec()),
+                }
+            },
+            Function::TruthTable(table) => (table.evaluate(input_values), vec![]),
+            Function::StateMachine(machine) => {
+                let bit_count = machine.state_bit_count();
+                let (data, clock) = (&input_values[..input_values.len() - 1], input_values[input_values.len() - 1]);
+
+                let mut state = to_number(&owned_values[..bit_count]);
+                if state >= machine.state_count() {
+                    state = 0;
+                }
+                if machine.edge().is_triggered(owned_values[bit_count], clock) {
+                    state = machine.next_state(state, data);
+                }
+
+                let mut new_owned_values = to_values(state, bit_count);
+                new_owned_values.push(clock);
+                (machine.output_values(state, data), new_owned_values)
+            },
         }
     }
 
@@ -113,10 +527,39 @@ impl Function {
             Function::Nor => 2,
             Function::Xnor => 2,
             Function::Circuit(circuit) => circuit.all_inputs().len(),
+            Function::Subcircuit(reference) => reference.circuit().map_or(0, |circuit| circuit.all_inputs().len()),
             Function::FlipFlopRS => 2,
-            Function::FlipFlopJK => 3,
-            Function::FlipFlopD => 2,
-            Function::FlipFlopT => 2,
+            Function::FlipFlopJK { .. } => 3,
+            Function::FlipFlopD { .. } => 2,
+            Function::FlipFlopT { .. } => 2,
+            Function::On => 0,
+            Function::Off => 0,
+            Function::Clock { .. } => 0,
+            Function::Multiplexer { select_lines } => saturating_power_of_two(*select_lines).saturating_add(*select_lines),
+            Function::Demultiplexer { select_lines } => 1 + select_lines,
+            Function::Decoder { width } => *width,
+            Function::Encoder { width } => saturating_power_of_two(*width),
+            Function::PriorityEncoder { width } => saturating_power_of_two(*width),
+            Function::HalfAdder => 2,
+            Function::FullAdder => 3,
+            Function::Adder { width } => 2 * width + 2,
+            Function::Alu { width } => 2 * width + 3,
+            Function::Counter { width, .. } => width + 5,
+            Function::ShiftRegisterSipo { .. } => 2,
+            Function::ShiftRegisterPiso { length, .. } => length + 3,
+            Function::ShiftRegisterUniversal { length, .. } => length + 5,
+            Function::FlipFlop { kind, options } => kind.data_value_count() + options.control_value_count(),
+            Function::Register { width, options } => width + options.control_value_count(),
+            Function::Ram { address_width, data_width, .. } => address_width + data_width + 2,
+            Function::Rom { address_width, .. } => *address_width,
+            Function::Comparator { width, .. } => 2 * width + 3,
+            Function::TriStateBuffer { width } => width + 1,
+            Function::OpenDrain { width } => *width,
+            Function::OpenSource { width } => *width,
+            Function::LatchD => 2,
+            Function::LatchSR => 3,
+            Function::TruthTable(table) => table.input_count(),
+            Function::StateMachine(machine) => machine.inputs().len() + 1,
         }
     }
 
@@ -130,10 +573,39 @@ impl Function {
             Function::Nor => 1,
             Function::Xnor => 1,
             Function::Circuit(circuit) => circuit.all_outputs().len(),
+            Function::Subcircuit(reference) => reference.circuit().map_or(0, |circuit| circuit.all_outputs().len()),
             Function::FlipFlopRS => 2,
-            Function::FlipFlopJK => 2,
-            Function::FlipFlopD => 2,
-            Function::FlipFlopT => 2,
+            Function::FlipFlopJK { .. } => 2,
+            Function::FlipFlopD { .. } => 2,
+            Function::FlipFlopT { .. } => 2,
+            Function::On => 1,
+            Function::Off => 1,
+            Function::Clock { .. } => 1,
+            Function::Multiplexer { .. } => 1,
+            Function::Demultiplexer { select_lines } => saturating_power_of_two(*select_lines),
+            Function::Decoder { width } => saturating_power_of_two(*width),
+            Function::Encoder { width } => width + 1,
+            Function::PriorityEncoder { width } => width + 1,
+            Function::HalfAdder => 2,
+            Function::FullAdder => 2,
+            Function::Adder { width } => width + 2,
+            Function::Alu { width } => width + 4,
+            Function::Counter { width, .. } => width + 1,
+            Function::ShiftRegisterSipo { length, .. } => *length,
+            Function::ShiftRegisterPiso { .. } => 1,
+            Function::ShiftRegisterUniversal { length, .. } => *length,
+            Function::FlipFlop { .. } => 2,
+            Function::Register { width, .. } => *width,
+            Function::Ram { data_width, .. } => *data_width,
+            Function::Rom { data_width, .. } => *data_width,
+            Function::Comparator { .. } => 3,
+            Function::TriStateBuffer { width } => *width,
+            Function::OpenDrain { width } => *width,
+            Function::OpenSource { width } => *width,
+            Function::LatchD => 2,
+            Function::LatchSR => 2,
+            Function::TruthTable(table) => table.output_count(),
+            Function::StateMachine(machine) => machine.outputs().len(),
         }
     }
 
@@ -147,21 +619,357 @@ impl Function {
             Function::Nor => 0,
             Function::Xnor => 0,
             Function::Circuit(_) => 0,
+            Function::Subcircuit(_) => 0,
             Function::FlipFlopRS => 1,
-            Function::FlipFlopJK => 2,
-            Function::FlipFlopD => 2,
-            Function::FlipFlopT => 2,
+            Function::FlipFlopJK { .. } => 2,
+            Function::FlipFlopD { .. } => 2,
+            Function::FlipFlopT { .. } => 2,
+            Function::On => 0,
+            Function::Off => 0,
+            Function::Clock { .. } => 1,
+            Function::Multiplexer { .. } => 0,
+            Function::Demultiplexer { .. } => 0,
+            Function::Decoder { .. } => 0,
+            Function::Encoder { .. } => 0,
+            Function::PriorityEncoder { .. } => 0,
+            Function::HalfAdder => 0,
+            Function::FullAdder => 0,
+            Function::Adder { .. } => 0,
+            Function::Alu { .. } => 0,
+            Function::Counter { width, .. } => width + 1,
+            Function::ShiftRegisterSipo { length, .. } => length + 1,
+            Function::ShiftRegisterPiso { length, .. } => length + 1,
+            Function::ShiftRegisterUniversal { length, .. } => length + 1,
+            Function::FlipFlop { .. } => 2,
+            Function::Register { width, .. } => width + 1,
+            Function::Ram { address_width, data_width, .. } => saturating_power_of_two(*address_width).saturating_mul(*data_width).saturating_add(1),
+            Function::Rom { .. } => 0,
+            Function::Comparator { .. } => 0,
+            Function::TriStateBuffer { width } => width + 1,
+            Function::OpenDrain { width } => *width,
+            Function::OpenSource { width } => *width,
+            Function::LatchD => 1,
+            Function::LatchSR => 1,
+            Function::TruthTable(_) => 0,
+            Function::StateMachine(machine) => machine.state_bit_count() + 1,
+        }
+    }
+
+    /// Checks that the function can be simulated, which includes that its
+    /// subcircuits are resolved. Counts and evaluation of invalid functions
+    /// are meaningless.
+    pub fn validate(&self) -> Result<(), FunctionError> {
+        match self {
+            Function::Circuit(circuit) => circuit.validate().map_err(|error| FunctionError::InvalidCircuit(Box::new(error))),
+            Function::Subcircuit(reference) => match reference.circuit() {
+                Some(circuit) => circuit.validate().map_err(|error| FunctionError::InvalidCircuit(Box::new(error))),
+                None => Err(FunctionError::UnresolvedSubcircuit { name: reference.name().to_owned(), version: reference.version() }),
+            },
+            Function::Counter { modulus: 0, .. } => Err(FunctionError::ZeroModulus),
+            Function::Counter { width, modulus, .. } if power_of_two(*width).is_some_and(|count_limit| *modulus > count_limit) => {
+                Err(FunctionError::ModulusTooLarge { modulus: *modulus, width: *width })
+            },
+            Function::ShiftRegisterSipo { length: 0, .. } | Function::ShiftRegisterPiso { length: 0, .. } | Function::ShiftRegisterUniversal { length: 0, .. } => {
+                Err(FunctionError::ZeroLength)
+            },
+            Function::Multiplexer { select_lines: width }
+            | Function::Demultiplexer { select_lines: width }
+            | Function::Decoder { width }
+            | Function::Encoder { width }
+            | Function::PriorityEncoder { width }
+            | Function::Rom { address_width: width, .. } if power_of_two(*width).is_none() => {
+                Err(FunctionError::TooWide { width: *width })
+            },
+            Function::StateMachine(machine) if machine.state_count() == 0 => Err(FunctionError::InvalidStateMachine(StateMachineError::NoStates)),
+            Function::Ram { data_width, .. } | Function::Rom { data_width, .. } if *data_width > usize::BITS as usize => {
+                Err(FunctionError::TooWide { width: *data_width })
+            },
+            Function::Ram { address_width, data_width, .. } => {
+                match power_of_two(*address_width).and_then(|words| words.checked_mul(*data_width)).and_then(|len| len.checked_add(1)) {
+                    Some(_) => Ok(()),
+                    None => Err(FunctionError::TooWide { width: *address_width }),
+                }
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the outputs of the function are buses that may have several drivers.
+    pub fn is_bus_driver(&self) -> bool {
+        matches!(self, Function::TriStateBuffer { .. } | Function::OpenDrain { .. } | Function::OpenSource { .. })
+    }
+
+    /// Returns for bus drivers the value driven onto each output, or `None`
+    /// where the output is not driven, and `None` for all other functions.
+    pub fn driven_values(&self, owned_values: &[Value]) -> Option<Vec<Option<Value>>> {
+        match self {
+            Function::TriStateBuffer { width } => {
+                let enable = owned_values[*width];
+                Some(owned_values[..*width].iter().map(|&value| (enable == Value::On).then_some(value)).collect())
+            },
+            Function::OpenDrain { .. } => Some(owned_values.iter().map(|&value| (value == Value::Off).then_some(Value::Off)).collect()),
+            Function::OpenSource { .. } => Some(owned_values.iter().map(|&value| (value == Value::On).then_some(Value::On)).collect()),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of words and their width for functions that keep
+    /// memory at the start of their owned values and `None` for all others.
+    pub fn memory_layout(&self) -> Option<(usize, usize)> {
+        match self {
+            Function::Ram { address_width, data_width, .. } => Some((saturating_power_of_two(*address_width), *data_width)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value a clock drives at the given simulator time and `None`
+    /// for every function that is not a clock.
+    pub fn clock_value(&self, time: usize) -> Option<Value> {
+        match self {
+            Function::Clock { period, duty_cycle } => {
+                if time % period.get() < *duty_cycle {
+                    Some(Value::On)
+                } else {
+                    Some(Value::Off)
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+impl FlipFlopKind {
+    fn data_value_count(&self) -> usize {
+        match self {
+            FlipFlopKind::JK => 2,
+            FlipFlopKind::D => 1,
+            FlipFlopKind::T => 1,
+        }
+    }
+}
+
+impl FlipFlopOptions {
+    /// Number of control inputs including the clock.
+    fn control_value_count(&self) -> usize {
+        1 + usize::from(self.enable) + 2 * usize::from(self.preset_clear)
+    }
+
+    /// Calculates the next state of one stored bit from the control inputs that
+    /// follow the data inputs. `clocked_state` is only called on an enabled clock edge.
+    fn next_state(&self, control_values: &[Value], old_clock: Value, state: Value, clocked_state: impl FnOnce() -> Value) -> Value {
+        let mut control_values = control_values.iter().copied();
+
+        let enable = if self.enable { control_values.next().unwrap() } else { Value::On };
+        let (preset, clear) = if self.preset_clear {
+            (control_values.next().unwrap(), control_values.next().unwrap())
+        } else {
+            (Value::Off, Value::Off)
+        };
+        let clock = control_values.next().unwrap();
+
+        if clear == Value::On {
+            Value::Off
+        } else if preset == Value::On {
+            Value::On
+        } else if self.edge.is_triggered(old_clock, clock) && enable == Value::On {
+            clocked_state()
+        } else {
+            state
         }
     }
 }
 
 impl Display for Function {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = format!("{self:?}").chars().take_while(|&ch| ch != '(').collect::<String>();
+        let name = format!("{self:?}").chars().take_while(|&ch| ch.is_alphanumeric()).collect::<String>();
         write!(f, "{name}")
     }
 }
 
+impl ClockEdge {
+    pub(crate) fn is_triggered(&self, old_clock: Value, clock: Value) -> bool {
+        match self {
+            ClockEdge::Rising => is_positiv_transient(old_clock, clock),
+            ClockEdge::Falling => is_positiv_transient(!old_clock, !clock),
+            ClockEdge::Dual => old_clock != clock,
+        }
+    }
+}
+
+impl Display for FunctionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FunctionError::UnresolvedSubcircuit { name, version } => write!(f, "subcircuit {name}@{version} has not been resolved"),
+            FunctionError::InvalidCircuit(error) => write!(f, "invalid circuit: {error}"),
+            FunctionError::ZeroModulus => write!(f, "the modulus of a counter must not be 0"),
+            FunctionError::ModulusTooLarge { modulus, width } => write!(f, "a modulus of {modulus} does not fit into {width} bits"),
+            FunctionError::ZeroLength => write!(f, "a shift register must store at least one bit"),
+            FunctionError::TooWide { width } => write!(f, "a width of {width} bits is too large"),
+            FunctionError::InvalidStateMachine(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for FunctionError {}
+
+impl Display for FlipFlopKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Display for ClockEdge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Deserializes a function like its derived implementation, but also accepts
+/// the unit variants `FlipFlopJK`, `FlipFlopD` and `FlipFlopT` of circuits
+/// saved before these flip-flops had a clock edge.
+pub(crate) fn deserialize_function<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Function, D::Error> {
+    let value = match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(name) if matches!(name.as_str(), "FlipFlopJK" | "FlipFlopD" | "FlipFlopT") => {
+            serde_json::Value::Object(serde_json::Map::from_iter([(name, serde_json::Value::Object(serde_json::Map::new()))]))
+        },
+        value => value,
+    };
+
+    Function::deserialize(value).map_err(serde::de::Error::custom)
+}
+
+/// `2^exponent`, if it fits into a `usize`.
+fn power_of_two(exponent: usize) -> Option<usize> {
+    u32::try_from(exponent).ok().and_then(|exponent| 1usize.checked_shl(exponent))
+}
+
+/// `2^exponent` or `usize::MAX` if it is too large, which only happens for
+/// functions that do not pass `Function::validate`.
+pub(crate) fn saturating_power_of_two(exponent: usize) -> usize {
+    power_of_two(exponent).unwrap_or(usize::MAX)
+}
+
+/// Whether bit `index` of the number is set, where bits beyond the width of a
+/// `usize` are never set.
+fn is_bit_set(number: usize, index: usize) -> bool {
+    u32::try_from(index).ok().and_then(|index| number.checked_shr(index)).is_some_and(|number| number & 1 == 1)
+}
+
+fn evaluate_circuit(circuit: &Circuit, input_values: &[Value]) -> Vec<Value> {
+    // the circuit has been validated together with the one containing it
+    let mut simulator = Simulator::for_evaluation(circuit.clone());
+
+    for (i, &value) in input_values.iter().enumerate() {
+        simulator.set_input(i, value);
+    }
+
+    let _ = simulator.simulate();
+
+    circuit.all_outputs().iter()
+        .map(|output| simulator.value_for_index(output.value_index()))
+        .collect()
+}
+
+/// Interprets the values as an unsigned number with the least significant bit first.
+pub(crate) fn to_number(values: &[Value]) -> usize {
+    values.iter().rev().fold(0, |acc, &value| (acc << 1) | usize::from(value == Value::On))
+}
+
+/// Interprets a `width` bit number as two's complement.
+fn to_signed(number: usize, width: usize) -> isize {
+    match power_of_two(width) {
+        Some(range) if width > 0 && is_bit_set(number, width - 1) => number.wrapping_sub(range) as isize,
+        _ => number as isize,
+    }
+}
+
+/// Converts the number into `width` values with the least significant bit first.
+pub(crate) fn to_values(number: usize, width: usize) -> Vec<Value> {
+    (0..width).map(|i| if is_bit_set(number, i) { Value::On } else { Value::Off }).collect()
+}
+
+/// `width` values of which only the one at `index` is `On`.
+fn one_hot(index: usize, width: usize) -> Vec<Value> {
+    (0..width).map(|i| if i == index { Value::On } else { Value::Off }).collect()
+}
+
+fn encoder_values(index: usize, width: usize, input_values: &[Value]) -> Vec<Value> {
+    let valid = input_values.iter().fold(Value::Off, |acc, &x| acc | x);
+
+    let mut values = to_values(index, width);
+    values.push(valid);
+    values
+}
+
+fn full_add(a: Value, b: Value, carry_in: Value) -> (Value, Value) {
+    let sum = a ^ b ^ carry_in;
+    let carry_out = (a & b) | (carry_in & (a ^ b));
+    (sum, carry_out)
+}
+
+/// Adds the values bit by bit with the least significant bit first and returns
+/// the sum together with carry out and signed overflow.
+fn ripple_add(a: &[Value], b: &[Value], carry_in: Value) -> (Vec<Value>, Value, Value) {
+    let mut sum = Vec::with_capacity(a.len());
+    let mut carry = carry_in;
+    let mut carry_into_last = carry_in;
+
+    for (&a, &b) in a.iter().zip(b.iter()) {
+        carry_into_last = carry;
+        let (value, carry_out) = full_add(a, b, carry);
+        sum.push(value);
+        carry = carry_out;
+    }
+
+    (sum, carry, carry ^ carry_into_last)
+}
+
+fn evaluate_alu(width: usize, input_values: &[Value]) -> Vec<Value> {
+    let a = &input_values[..width];
+    let b = &input_values[width..2 * width];
+    let opcode = to_number(&input_values[2 * width..]);
+
+    let inverted_b: Vec<_> = b.iter().map(|&value| !value).collect();
+    let shift = to_number(b);
+    let shifted_out = |index: Option<usize>| index.and_then(|index| a.get(index).copied()).unwrap_or(Value::Off);
+
+    let (mut values, carry, overflow) = match opcode {
+        0 => ripple_add(a, b, Value::Off),
+        1 | 7 => ripple_add(a, &inverted_b, Value::On),
+        2 => (a.iter().zip(b).map(|(&a, &b)| a & b).collect(), Value::Off, Value::Off),
+        3 => (a.iter().zip(b).map(|(&a, &b)| a | b).collect(), Value::Off, Value::Off),
+        4 => (a.iter().zip(b).map(|(&a, &b)| a ^ b).collect(), Value::Off, Value::Off),
+        5 => {
+            let values = (0..width).map(|i| i.checked_sub(shift).map_or(Value::Off, |i| a[i])).collect();
+            (values, shifted_out(width.checked_sub(shift).filter(|_| shift != 0)), Value::Off)
+        },
+        _ => {
+            let values = (0..width).map(|i| a.get(i + shift).copied().unwrap_or(Value::Off)).collect();
+            (values, shifted_out(shift.checked_sub(1)), Value::Off)
+        },
+    };
+
+    let zero = !values.iter().fold(Value::Off, |acc, &x| acc | x);
+    let negative = values.last().copied().unwrap_or(Value::Off);
+
+    if opcode == 7 {
+        values = vec![Value::Off; width];
+    }
+
+    values.extend([zero, carry, negative, overflow]);
+    values
+}
+
+/// Moves every value to the next higher index and puts `serial_in` at index 0.
+fn shift_up(state: &[Value], serial_in: Value) -> Vec<Value> {
+    std::iter::once(serial_in).chain(state[..state.len() - 1].iter().copied()).collect()
+}
+
+/// Moves every value to the next lower index and puts `serial_in` at the highest index.
+fn shift_down(state: &[Value], serial_in: Value) -> Vec<Value> {
+    state[1..].iter().copied().chain(std::iter::once(serial_in)).collect()
+}
+
 fn is_positiv_transient(old_value: Value, new_value: Value) -> bool {
     old_value != new_value && new_value == Value::On
 }
@@ -170,7 +978,7 @@ fn is_positiv_transient(old_value: Value, new_value: Value) -> bool {
 mod tests {
     use itertools::Itertools;
 
-    use self::util::{ClockState, tripple_input, dual_input};
+    use self::util::{ClockState, CounterControl, tripple_input, dual_input};
 
     use super::*;
 
@@ -323,13 +1131,13 @@ mod tests {
 
     #[test]
     fn flip_flop_jk() {
-        let jk = Function::FlipFlopJK;
+        let jk = Function::FlipFlopJK { edge: ClockEdge::Rising };
 
         let on_off =  &[Value::On,  Value::Off];
         let off_on =  &[Value::Off, Value::On];
         let on_on =   &[Value::On,  Value::On];
 
-        let base = vec![Value::On, Value::On, Value::On, Value::Off, Value::Off, Value::Off];
+        let base = [Value::On, Value::On, Value::On, Value::Off, Value::Off, Value::Off];
         let j_k_state_combinations: Vec<_> = base.iter().permutations(3).unique().collect();
 
         // in all these cases the output value should not change an thus be the same as the old state
@@ -398,13 +1206,13 @@ mod tests {
 
     #[test]
     fn flip_flop_d() {
-        let d = Function::FlipFlopD;
+        let d = Function::FlipFlopD { edge: ClockEdge::Rising };
 
         let on_off =  &[Value::On,  Value::Off];
         let off_on =  &[Value::Off, Value::On];
         let on_on =   &[Value::On,  Value::On];
 
-        let base = vec![Value::On, Value::On, Value::Off, Value::Off];
+        let base = [Value::On, Value::On, Value::Off, Value::Off];
         let d_state_combinations: Vec<_> = base.iter().permutations(2).unique().collect();
 
         // in all these cases the output value should not change an thus be the same as the old state
@@ -452,13 +1260,13 @@ mod tests {
 
     #[test]
     fn flip_flop_t() {
-        let t = Function::FlipFlopT;
+        let t = Function::FlipFlopT { edge: ClockEdge::Rising };
 
         let on_off =  &[Value::On,  Value::Off];
         let off_on =  &[Value::Off, Value::On];
         let on_on =   &[Value::On,  Value::On];
 
-        let base = vec![Value::On, Value::On, Value::Off, Value::Off];
+        let base = [Value::On, Value::On, Value::Off, Value::Off];
         let d_state_combinations: Vec<_> = base.iter().permutations(2).unique().collect();
 
         // in all these cases the output value should not change an thus be the same as the old state
@@ -504,6 +1312,1033 @@ mod tests {
         assert_eq!(owned_values, off_on);
     }
 
+    #[test]
+    fn on() {
+        let on = Function::On;
+
+        assert_eq!(on.evaluate(&[], &[]), (vec![Value::On], vec![]));
+        assert_eq!(on.clock_value(0), None);
+    }
+
+    #[test]
+    fn off() {
+        let off = Function::Off;
+
+        assert_eq!(off.evaluate(&[], &[]), (vec![Value::Off], vec![]));
+        assert_eq!(off.clock_value(0), None);
+    }
+
+    #[test]
+    fn clock() {
+        let clock = Function::Clock { period: NonZeroUsize::new(4).unwrap(), duty_cycle: 1 };
+
+        // the output is whatever level the simulator stored for the current time
+        assert_eq!(clock.evaluate(&[], &[Value::On]),  (vec![Value::On],  vec![Value::On]));
+        assert_eq!(clock.evaluate(&[], &[Value::Off]), (vec![Value::Off], vec![Value::Off]));
+
+        let levels: Vec<_> = (0..8).map(|time| clock.clock_value(time).unwrap()).collect();
+        assert_eq!(levels, [Value::On, Value::Off, Value::Off, Value::Off, Value::On, Value::Off, Value::Off, Value::Off]);
+
+        let always_on = Function::Clock { period: NonZeroUsize::new(2).unwrap(), duty_cycle: 2 };
+        assert!((0..4).all(|time| always_on.clock_value(time) == Some(Value::On)));
+
+        let always_off = Function::Clock { period: NonZeroUsize::new(2).unwrap(), duty_cycle: 0 };
+        assert!((0..4).all(|time| always_off.clock_value(time) == Some(Value::Off)));
+    }
+
+    #[test]
+    fn multiplexer() {
+        let mux = Function::Multiplexer { select_lines: 2 };
+        assert_eq!(mux.input_value_count(), 6);
+        assert_eq!(mux.output_value_count(), 1);
+
+        // every data combination for every selection
+        for data in 0..16 {
+            for select in 0..4 {
+                let mut input_values = to_values(data, 4);
+                input_values.extend(to_values(select, 2));
+
+                let expected = to_values(data >> select, 1);
+                assert_eq!(mux.evaluate(&input_values, &[]), (expected, vec![]));
+            }
+        }
+    }
+
+    #[test]
+    fn demultiplexer() {
+        let demux = Function::Demultiplexer { select_lines: 2 };
+        assert_eq!(demux.input_value_count(), 3);
+        assert_eq!(demux.output_value_count(), 4);
+
+        for select in 0..4 {
+            let mut input_values = vec![Value::On];
+            input_values.extend(to_values(select, 2));
+            assert_eq!(demux.evaluate(&input_values, &[]), (to_values(1 << select, 4), vec![]));
+
+            input_values[0] = Value::Off;
+            assert_eq!(demux.evaluate(&input_values, &[]), (to_values(0, 4), vec![]));
+        }
+    }
+
+    #[test]
+    fn widths_beyond_usize() {
+        assert_eq!(Function::Multiplexer { select_lines: 63 }.validate(), Ok(()));
+        assert_eq!(Function::Multiplexer { select_lines: 64 }.validate(), Err(FunctionError::TooWide { width: 64 }));
+        assert_eq!(Function::Demultiplexer { select_lines: 100 }.validate(), Err(FunctionError::TooWide { width: 100 }));
+        assert_eq!(Function::Decoder { width: 64 }.validate(), Err(FunctionError::TooWide { width: 64 }));
+        assert_eq!(Function::Encoder { width: 64 }.validate(), Err(FunctionError::TooWide { width: 64 }));
+        assert_eq!(Function::PriorityEncoder { width: 64 }.validate(), Err(FunctionError::TooWide { width: 64 }));
+        assert_eq!(Function::Ram { address_width: 62, data_width: 1, edge: ClockEdge::Rising }.validate(), Ok(()));
+        assert_eq!(Function::Ram { address_width: 62, data_width: 4, edge: ClockEdge::Rising }.validate(), Err(FunctionError::TooWide { width: 62 }));
+        assert_eq!(Function::Ram { address_width: 1, data_width: 64, edge: ClockEdge::Rising }.validate(), Ok(()));
+        assert_eq!(Function::Ram { address_width: 1, data_width: 65, edge: ClockEdge::Rising }.validate(), Err(FunctionError::TooWide { width: 65 }));
+        assert_eq!(Function::Rom { address_width: 1, data_width: 65, image: RomImage::new("".into(), crate::memory::MemoryFormat::Hex) }.validate(), Err(FunctionError::TooWide { width: 65 }));
+        assert_eq!(Function::Ram { address_width: 64, data_width: 1, edge: ClockEdge::Rising }.validate(), Err(FunctionError::TooWide { width: 64 }));
+
+        // the counts of invalid functions saturate instead of overflowing
+        assert_eq!(Function::Multiplexer { select_lines: 64 }.input_value_count(), usize::MAX);
+        assert_eq!(Function::Decoder { width: 64 }.output_value_count(), usize::MAX);
+
+        // loaded circuits are checked when they are simulated
+        let mut circuit = Circuit::new();
+        let inputs: Vec<_> = (0..2).map(|_| circuit.add_input().1).collect();
+        circuit.add_component(Function::Decoder { width: 2 }, inputs).unwrap();
+        let serialized = serde_json::to_string(&circuit).unwrap().replace("\"width\":2", "\"width\":64");
+        let loaded: Circuit = serde_json::from_str(&serialized).unwrap();
+        assert!(matches!(Simulator::new(loaded), Err(CircuitError::InvalidComponent { error: FunctionError::TooWide { width: 64 }, .. })));
+
+        // selections of 64 and more do not overflow either
+        let decoder = Function::Decoder { width: 7 };
+        let (output_values, _) = decoder.evaluate(&to_values(100, 7), &[]);
+        assert_eq!(output_values.iter().positions(|&value| value == Value::On).collect::<Vec<_>>(), [100]);
+
+        let demux = Function::Demultiplexer { select_lines: 7 };
+        let (output_values, _) = demux.evaluate(&[vec![Value::On], to_values(127, 7)].concat(), &[]);
+        assert_eq!(output_values.iter().positions(|&value| value == Value::On).collect::<Vec<_>>(), [127]);
+
+        assert_eq!(to_values(1, 66), [vec![Value::On], vec![Value::Off; 65]].concat());
+        assert_eq!(to_signed(usize::MAX, 64), -1);
+        assert_eq!(to_signed(1 << 62, 63), -(1 << 62));
+    }
+
+    #[test]
+    fn decoder() {
+        let decoder = Function::Decoder { width: 1 };
+        assert_eq!(decoder.input_value_count(), 1);
+        assert_eq!(decoder.output_value_count(), 2);
+
+        assert_eq!(decoder.evaluate(&[Value::Off], &[]), (vec![Value::On,  Value::Off], vec![]));
+        assert_eq!(decoder.evaluate(&[Value::On],  &[]), (vec![Value::Off, Value::On],  vec![]));
+
+        // the inputs are the index with the least significant bit first
+        let decoder = Function::Decoder { width: 2 };
+        assert_eq!(decoder.input_value_count(), 2);
+        assert_eq!(decoder.output_value_count(), 4);
+
+        assert_eq!(decoder.evaluate(&[Value::Off, Value::Off], &[]), (vec![Value::On,  Value::Off, Value::Off, Value::Off], vec![]));
+        assert_eq!(decoder.evaluate(&[Value::On,  Value::Off], &[]), (vec![Value::Off, Value::On,  Value::Off, Value::Off], vec![]));
+        assert_eq!(decoder.evaluate(&[Value::Off, Value::On],  &[]), (vec![Value::Off, Value::Off, Value::On,  Value::Off], vec![]));
+        assert_eq!(decoder.evaluate(&[Value::On,  Value::On],  &[]), (vec![Value::Off, Value::Off, Value::Off, Value::On],  vec![]));
+
+        let decoder = Function::Decoder { width: 3 };
+        assert_eq!(decoder.output_value_count(), 8);
+
+        assert_eq!(
+            decoder.evaluate(&[Value::Off, Value::Off, Value::Off], &[]),
+            (vec![Value::On, Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::Off], vec![])
+        );
+        assert_eq!(
+            decoder.evaluate(&[Value::On, Value::Off, Value::On], &[]),
+            (vec![Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::On, Value::Off, Value::Off], vec![])
+        );
+        assert_eq!(
+            decoder.evaluate(&[Value::On, Value::On, Value::On], &[]),
+            (vec![Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::On], vec![])
+        );
+    }
+
+    #[test]
+    fn encoder() {
+        // the outputs are the index with the least significant bit first, then valid
+        let encoder = Function::Encoder { width: 1 };
+        assert_eq!(encoder.input_value_count(), 2);
+        assert_eq!(encoder.output_value_count(), 2);
+
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::Off], &[]), (vec![Value::Off, Value::Off], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::Off], &[]), (vec![Value::Off, Value::On],  vec![]));
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::On],  &[]), (vec![Value::On,  Value::On],  vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::On],  &[]), (vec![Value::On,  Value::On],  vec![]));
+
+        let encoder = Function::Encoder { width: 2 };
+        assert_eq!(encoder.input_value_count(), 4);
+        assert_eq!(encoder.output_value_count(), 3);
+
+        // no active input is not valid
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::Off, Value::Off, Value::Off], &[]), (vec![Value::Off, Value::Off, Value::Off], vec![]));
+
+        // a single active input
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::Off, Value::Off, Value::Off], &[]), (vec![Value::Off, Value::Off, Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::On,  Value::Off, Value::Off], &[]), (vec![Value::On,  Value::Off, Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::Off, Value::On,  Value::Off], &[]), (vec![Value::Off, Value::On,  Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::Off, Value::Off, Value::On],  &[]), (vec![Value::On,  Value::On,  Value::On], vec![]));
+
+        // the indices of several active inputs get or'ed together
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::On,  Value::On,  Value::Off], &[]), (vec![Value::On,  Value::On,  Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::Off, Value::On,  Value::Off], &[]), (vec![Value::Off, Value::On,  Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::On,  Value::On,  Value::On],  &[]), (vec![Value::On,  Value::On,  Value::On], vec![]));
+
+        let encoder = Function::Encoder { width: 3 };
+        assert_eq!(encoder.input_value_count(), 8);
+        assert_eq!(encoder.output_value_count(), 4);
+
+        assert_eq!(
+            encoder.evaluate(&[Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::On, Value::Off, Value::Off], &[]),
+            (vec![Value::On, Value::Off, Value::On, Value::On], vec![])
+        );
+        assert_eq!(
+            encoder.evaluate(&[Value::Off, Value::Off, Value::Off, Value::On, Value::On, Value::Off, Value::Off, Value::Off], &[]),
+            (vec![Value::On, Value::On, Value::On, Value::On], vec![])
+        );
+    }
+
+    #[test]
+    fn priority_encoder() {
+        let encoder = Function::PriorityEncoder { width: 1 };
+        assert_eq!(encoder.input_value_count(), 2);
+        assert_eq!(encoder.output_value_count(), 2);
+
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::Off], &[]), (vec![Value::Off, Value::Off], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::Off], &[]), (vec![Value::Off, Value::On],  vec![]));
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::On],  &[]), (vec![Value::On,  Value::On],  vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::On],  &[]), (vec![Value::On,  Value::On],  vec![]));
+
+        let encoder = Function::PriorityEncoder { width: 2 };
+        assert_eq!(encoder.input_value_count(), 4);
+        assert_eq!(encoder.output_value_count(), 3);
+
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::Off, Value::Off, Value::Off], &[]), (vec![Value::Off, Value::Off, Value::Off], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::Off, Value::Off, Value::Off], &[]), (vec![Value::Off, Value::Off, Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::Off, Value::Off, Value::On],  &[]), (vec![Value::On,  Value::On,  Value::On], vec![]));
+
+        // the highest active index wins
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::On,  Value::Off, Value::Off], &[]), (vec![Value::On,  Value::Off, Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::Off, Value::On,  Value::Off], &[]), (vec![Value::Off, Value::On,  Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::Off, Value::On,  Value::On,  Value::Off], &[]), (vec![Value::Off, Value::On,  Value::On], vec![]));
+        assert_eq!(encoder.evaluate(&[Value::On,  Value::On,  Value::On,  Value::On],  &[]), (vec![Value::On,  Value::On,  Value::On], vec![]));
+
+        let encoder = Function::PriorityEncoder { width: 3 };
+        assert_eq!(encoder.input_value_count(), 8);
+        assert_eq!(encoder.output_value_count(), 4);
+
+        assert_eq!(
+            encoder.evaluate(&[Value::On, Value::Off, Value::Off, Value::Off, Value::Off, Value::On, Value::Off, Value::Off], &[]),
+            (vec![Value::On, Value::Off, Value::On, Value::On], vec![])
+        );
+        assert_eq!(
+            encoder.evaluate(&[Value::Off, Value::On, Value::Off, Value::Off, Value::Off, Value::Off, Value::On, Value::Off], &[]),
+            (vec![Value::Off, Value::On, Value::On, Value::On], vec![])
+        );
+    }
+
+    #[test]
+    fn half_adder() {
+        let half_adder = Function::HalfAdder;
+
+        assert_eq!(half_adder.evaluate(&[Value::Off, Value::Off], &[]), (vec![Value::Off, Value::Off], vec![]));
+        assert_eq!(half_adder.evaluate(&[Value::On,  Value::Off], &[]), (vec![Value::On,  Value::Off], vec![]));
+        assert_eq!(half_adder.evaluate(&[Value::Off, Value::On],  &[]), (vec![Value::On,  Value::Off], vec![]));
+        assert_eq!(half_adder.evaluate(&[Value::On,  Value::On],  &[]), (vec![Value::Off, Value::On],  vec![]));
+    }
+
+    #[test]
+    fn full_adder() {
+        let full_adder = Function::FullAdder;
+
+        for input in 0..8 {
+            let input_values = to_values(input, 3);
+            let sum = input_values.iter().filter(|&&value| value == Value::On).count();
+            assert_eq!(full_adder.evaluate(&input_values, &[]), (to_values(sum, 2), vec![]));
+        }
+    }
+
+    #[test]
+    fn adder() {
+        let width = 3;
+        let adder = Function::Adder { width };
+        assert_eq!(adder.input_value_count(), 8);
+        assert_eq!(adder.output_value_count(), 5);
+
+        let signed = |number: usize| if number >= 4 { number as i32 - 8 } else { number as i32 };
+
+        for a in 0..8 {
+            for b in 0..8 {
+                for carry in 0..2 {
+                    let mut input_values = to_values(a, width);
+                    input_values.extend(to_values(b, width));
+                    input_values.extend(to_values(carry, 1));
+
+                    // addition
+                    input_values.push(Value::Off);
+                    let result = a + b + carry;
+                    let overflow = !(-4..4).contains(&(signed(a) + signed(b) + carry as i32));
+
+                    let mut expected = to_values(result % 8, width);
+                    expected.push(to_values(result >> width, 1)[0]);
+                    expected.push(if overflow { Value::On } else { Value::Off });
+                    assert_eq!(adder.evaluate(&input_values, &[]), (expected, vec![]));
+
+                    // subtraction with carry in used as borrow in
+                    input_values[2 * width + 1] = Value::On;
+                    let result = (a + 16 - b - carry) % 8;
+                    let no_borrow = a >= b + carry;
+                    let overflow = !(-4..4).contains(&(signed(a) - signed(b) - carry as i32));
+
+                    let mut expected = to_values(result, width);
+                    expected.push(if no_borrow { Value::On } else { Value::Off });
+                    expected.push(if overflow { Value::On } else { Value::Off });
+                    assert_eq!(adder.evaluate(&input_values, &[]), (expected, vec![]));
+                }
+            }
+        }
+
+        // corner cases of carry and overflow, with the inputs a, b, carry in and
+        // subtract and the outputs sum, carry out and overflow
+        let cases = [
+            // 3 + 1 leaves the signed range without a carry
+            ([Value::On,  Value::On,  Value::Off, Value::On,  Value::Off, Value::Off, Value::Off, Value::Off], [Value::Off, Value::Off, Value::On,  Value::Off, Value::On]),
+            // 7 + 1 carries but is -1 + 1 in the signed range
+            ([Value::On,  Value::On,  Value::On,  Value::On,  Value::Off, Value::Off, Value::Off, Value::Off], [Value::Off, Value::Off, Value::Off, Value::On,  Value::Off]),
+            // -4 + -4 carries and overflows
+            ([Value::Off, Value::Off, Value::On,  Value::Off, Value::Off, Value::On,  Value::Off, Value::Off], [Value::Off, Value::Off, Value::Off, Value::On,  Value::On]),
+            // 3 + 0 with carry in overflows
+            ([Value::On,  Value::On,  Value::Off, Value::Off, Value::Off, Value::Off, Value::On,  Value::Off], [Value::Off, Value::Off, Value::On,  Value::Off, Value::On]),
+            // 0 - 1 borrows, so the carry out is Off
+            ([Value::Off, Value::Off, Value::Off, Value::On,  Value::Off, Value::Off, Value::Off, Value::On],  [Value::On,  Value::On,  Value::On,  Value::Off, Value::Off]),
+            // -4 - 1 overflows without borrowing
+            ([Value::Off, Value::Off, Value::On,  Value::On,  Value::Off, Value::Off, Value::Off, Value::On],  [Value::On,  Value::On,  Value::Off, Value::On,  Value::On]),
+            // 3 - -4 borrows and overflows
+            ([Value::On,  Value::On,  Value::Off, Value::Off, Value::Off, Value::On,  Value::Off, Value::On],  [Value::On,  Value::On,  Value::On,  Value::Off, Value::On]),
+            // 2 - 1 with borrow in is exactly 0
+            ([Value::Off, Value::On,  Value::Off, Value::On,  Value::Off, Value::Off, Value::On,  Value::On],  [Value::Off, Value::Off, Value::Off, Value::On,  Value::Off]),
+        ];
+
+        for (input_values, output_values) in cases {
+            assert_eq!(adder.evaluate(&input_values, &[]), (output_values.to_vec(), vec![]), "inputs: {input_values:?}");
+        }
+    }
+
+    #[test]
+    fn alu() {
+        let width = 3;
+        let alu = Function::Alu { width };
+        assert_eq!(alu.input_value_count(), 9);
+        assert_eq!(alu.output_value_count(), 7);
+
+        let signed = |number: usize| if number >= 4 { number as i32 - 8 } else { number as i32 };
+        let flag = |condition: bool| if condition { Value::On } else { Value::Off };
+
+        for a in 0..8 {
+            for b in 0..8 {
+                for opcode in 0..8 {
+                    let mut input_values = to_values(a, width);
+                    input_values.extend(to_values(b, width));
+                    input_values.extend(to_values(opcode, 3));
+
+                    let (result, carry, overflow) = match opcode {
+                        0 => (a + b, a + b >= 8, !(-4..4).contains(&(signed(a) + signed(b)))),
+                        1 | 7 => (a + 8 - b, a >= b, !(-4..4).contains(&(signed(a) - signed(b)))),
+                        2 => (a & b, false, false),
+                        3 => (a | b, false, false),
+                        4 => (a ^ b, false, false),
+                        5 => ((a << b) & 7, b != 0 && b <= width && a >> (width - b) & 1 == 1, false),
+                        _ => (a >> b, b != 0 && a >> (b - 1) & 1 == 1, false),
+                    };
+                    let result = result % 8;
+
+                    let mut expected = if opcode == 7 { to_values(0, width) } else { to_values(result, width) };
+                    expected.extend([flag(result == 0), flag(carry), flag(result >= 4), flag(overflow)]);
+
+                    assert_eq!(alu.evaluate(&input_values, &[]), (expected, vec![]), "a: {a}, b: {b}, opcode: {opcode}");
+                }
+            }
+        }
+
+        // corner cases of the flags, with the inputs a, b and opcode and the
+        // outputs result, zero, carry, negative and overflow
+        let cases = [
+            // ADD 3 + 1 overflows into a negative result
+            ([Value::On,  Value::On,  Value::Off, Value::On,  Value::Off, Value::Off, Value::Off, Value::Off, Value::Off], [Value::Off, Value::Off, Value::On,  Value::Off, Value::Off, Value::On,  Value::On]),
+            // ADD 7 + 1 carries into zero
+            ([Value::On,  Value::On,  Value::On,  Value::On,  Value::Off, Value::Off, Value::Off, Value::Off, Value::Off], [Value::Off, Value::Off, Value::Off, Value::On,  Value::On,  Value::Off, Value::Off]),
+            // SUB 0 - 1 borrows
+            ([Value::Off, Value::Off, Value::Off, Value::On,  Value::Off, Value::Off, Value::On,  Value::Off, Value::Off], [Value::On,  Value::On,  Value::On,  Value::Off, Value::Off, Value::On,  Value::Off]),
+            // SUB -4 - 1 overflows
+            ([Value::Off, Value::Off, Value::On,  Value::On,  Value::Off, Value::Off, Value::On,  Value::Off, Value::Off], [Value::On,  Value::On,  Value::Off, Value::Off, Value::On,  Value::Off, Value::On]),
+            // SHL 6 by 1 shifts a set bit out into the carry
+            ([Value::Off, Value::On,  Value::On,  Value::On,  Value::Off, Value::Off, Value::On,  Value::Off, Value::On],  [Value::Off, Value::Off, Value::On,  Value::Off, Value::On,  Value::On,  Value::Off]),
+            // SHL 1 by the full width moves bit 0 into the carry
+            ([Value::On,  Value::Off, Value::Off, Value::On,  Value::On,  Value::Off, Value::On,  Value::Off, Value::On],  [Value::Off, Value::Off, Value::Off, Value::On,  Value::On,  Value::Off, Value::Off]),
+            // SHR 3 by 1 moves bit 0 into the carry
+            ([Value::On,  Value::On,  Value::Off, Value::On,  Value::Off, Value::Off, Value::Off, Value::On,  Value::On],  [Value::On,  Value::Off, Value::Off, Value::Off, Value::On,  Value::Off, Value::Off]),
+            // SHR 7 by 4 shifts everything out and leaves no carry
+            ([Value::On,  Value::On,  Value::On,  Value::Off, Value::Off, Value::On,  Value::Off, Value::On,  Value::On],  [Value::Off, Value::Off, Value::Off, Value::On,  Value::Off, Value::Off, Value::Off]),
+            // CMP 2 with 2 is zero without a borrow
+            ([Value::Off, Value::On,  Value::Off, Value::Off, Value::On,  Value::Off, Value::On,  Value::On,  Value::On],  [Value::Off, Value::Off, Value::Off, Value::On,  Value::On,  Value::Off, Value::Off]),
+            // CMP 1 with 2 borrows and is negative
+            ([Value::On,  Value::Off, Value::Off, Value::Off, Value::On,  Value::Off, Value::On,  Value::On,  Value::On],  [Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::On,  Value::Off]),
+        ];
+
+        for (input_values, output_values) in cases {
+            assert_eq!(alu.evaluate(&input_values, &[]), (output_values.to_vec(), vec![]), "inputs: {input_values:?}");
+        }
+    }
+
+    #[test]
+    fn counter() {
+        let width = 3;
+        let counter = Function::Counter { width, modulus: 6, edge: ClockEdge::Rising };
+        assert_eq!(counter.input_value_count(), 8);
+        assert_eq!(counter.output_value_count(), 4);
+        assert_eq!(counter.owned_value_count(), 4);
+
+        let count_of = |values: &[Value]| to_number(&values[..width]);
+        let mut owned_values = vec![Value::Off; 4];
+
+        // counting up wraps around at the modulus and signals the carry at the last count
+        let mut counts = vec![];
+        for _ in 0..7 {
+            let (output_values, new_owned_values) = util::counter_pulse(&counter, 0, CounterControl::Up, &owned_values);
+            counts.push((count_of(&output_values), output_values[width]));
+            owned_values = new_owned_values;
+        }
+        assert_eq!(counts, [(1, Value::Off), (2, Value::Off), (3, Value::Off), (4, Value::Off), (5, Value::On), (0, Value::Off), (1, Value::Off)]);
+
+        // counting down
+        let mut counts = vec![];
+        for _ in 0..3 {
+            let (output_values, new_owned_values) = util::counter_pulse(&counter, 0, CounterControl::Down, &owned_values);
+            counts.push((count_of(&output_values), output_values[width]));
+            owned_values = new_owned_values;
+        }
+        assert_eq!(counts, [(0, Value::On), (5, Value::Off), (4, Value::Off)]);
+
+        // a disabled counter keeps its count
+        let (output_values, owned_values) = util::counter_pulse(&counter, 0, CounterControl::Disabled, &owned_values);
+        assert_eq!(count_of(&output_values), 4);
+        assert_eq!(output_values[width], Value::Off);
+
+        // loading happens on the clock edge
+        let (output_values, owned_values) = util::counter_pulse(&counter, 2, CounterControl::Load, &owned_values);
+        assert_eq!(count_of(&output_values), 2);
+
+        // reset does not wait for the clock
+        let mut input_values = to_values(0, width);
+        input_values.extend([Value::Off, Value::On, Value::On, Value::On, Value::On]);
+        let (output_values, owned_values) = counter.evaluate(&input_values, &owned_values);
+        assert_eq!(count_of(&output_values), 0);
+        assert_eq!(owned_values, [Value::Off, Value::Off, Value::Off, Value::On]);
+
+        // and takes precedence over counting
+        let (output_values, _) = util::counter_pulse(&counter, 0, CounterControl::UpWhileReset, &owned_values);
+        assert_eq!(count_of(&output_values), 0);
+    }
+
+    #[test]
+    fn counter_modulus() {
+        assert_eq!(Function::Counter { width: 3, modulus: 8, edge: ClockEdge::Rising }.validate(), Ok(()));
+        assert_eq!(Function::Counter { width: 64, modulus: usize::MAX, edge: ClockEdge::Rising }.validate(), Ok(()));
+        assert_eq!(Function::Counter { width: 3, modulus: 0, edge: ClockEdge::Rising }.validate(), Err(FunctionError::ZeroModulus));
+        assert_eq!(Function::Counter { width: 3, modulus: 9, edge: ClockEdge::Rising }.validate(), Err(FunctionError::ModulusTooLarge { modulus: 9, width: 3 }));
+
+        let mut circuit = Circuit::new();
+        let inputs: Vec<_> = (0..7).map(|_| circuit.add_input().1).collect();
+        assert_eq!(
+            circuit.add_component(Function::Counter { width: 2, modulus: 0, edge: ClockEdge::Rising }, inputs.clone()),
+            Err(CircuitError::InvalidFunction(FunctionError::ZeroModulus))
+        );
+        assert_eq!(
+            circuit.add_component(Function::Counter { width: 2, modulus: 5, edge: ClockEdge::Rising }, inputs.clone()),
+            Err(CircuitError::InvalidFunction(FunctionError::ModulusTooLarge { modulus: 5, width: 2 }))
+        );
+
+        // loaded circuits are checked when they are simulated
+        circuit.add_component(Function::Counter { width: 2, modulus: 4, edge: ClockEdge::Rising }, inputs).unwrap();
+        let serialized = serde_json::to_string(&circuit).unwrap().replace("\"modulus\":4", "\"modulus\":0");
+        let loaded: Circuit = serde_json::from_str(&serialized).unwrap();
+        assert!(matches!(Simulator::new(loaded), Err(CircuitError::InvalidComponent { component_index: 0, error: FunctionError::ZeroModulus })));
+    }
+
+    #[test]
+    fn shift_register_length() {
+        assert_eq!(Function::ShiftRegisterSipo { length: 1, edge: ClockEdge::Rising }.validate(), Ok(()));
+        assert_eq!(Function::ShiftRegisterSipo { length: 0, edge: ClockEdge::Rising }.validate(), Err(FunctionError::ZeroLength));
+        assert_eq!(Function::ShiftRegisterPiso { length: 0, edge: ClockEdge::Rising }.validate(), Err(FunctionError::ZeroLength));
+        assert_eq!(Function::ShiftRegisterUniversal { length: 0, edge: ClockEdge::Rising }.validate(), Err(FunctionError::ZeroLength));
+
+        let mut circuit = Circuit::new();
+        let inputs: Vec<_> = (0..3).map(|_| circuit.add_input().1).collect();
+        assert_eq!(
+            circuit.add_component(Function::ShiftRegisterPiso { length: 0, edge: ClockEdge::Rising }, inputs),
+            Err(CircuitError::InvalidFunction(FunctionError::ZeroLength))
+        );
+    }
+
+    #[test]
+    fn shift_register_sipo() {
+        let sipo = Function::ShiftRegisterSipo { length: 3, edge: ClockEdge::Rising };
+        assert_eq!(sipo.input_value_count(), 2);
+        assert_eq!(sipo.output_value_count(), 3);
+        assert_eq!(sipo.owned_value_count(), 4);
+
+        let mut owned_values = vec![Value::Off; 4];
+        let mut outputs = vec![];
+
+        for serial_in in [Value::On, Value::Off, Value::On, Value::On] {
+            let (output_values, new_owned_values) = util::clock_pulse(&sipo, vec![serial_in, Value::Off], &owned_values);
+            outputs.push(output_values);
+            owned_values = new_owned_values;
+        }
+
+        assert_eq!(outputs, [
+            [Value::On,  Value::Off, Value::Off],
+            [Value::Off, Value::On,  Value::Off],
+            [Value::On,  Value::Off, Value::On],
+            [Value::On,  Value::On,  Value::Off],
+        ]);
+
+        // without a clock edge nothing is shifted
+        let (output_values, owned_values) = sipo.evaluate(&[Value::Off, Value::On], &owned_values);
+        assert_eq!(output_values, [Value::On, Value::On, Value::Off]);
+        assert_eq!(owned_values, [Value::On, Value::On, Value::Off, Value::On]);
+    }
+
+    #[test]
+    fn shift_register_piso() {
+        let piso = Function::ShiftRegisterPiso { length: 3, edge: ClockEdge::Rising };
+        assert_eq!(piso.input_value_count(), 6);
+        assert_eq!(piso.output_value_count(), 1);
+        assert_eq!(piso.owned_value_count(), 4);
+
+        let owned_values = vec![Value::Off; 4];
+
+        // load 0b011 and shift it out starting with the highest index
+        let (output_values, mut owned_values) = util::clock_pulse(&piso, vec![Value::On, Value::On, Value::Off, Value::On, Value::Off, Value::Off], &owned_values);
+        let mut outputs = vec![output_values[0]];
+
+        for _ in 0..3 {
+            let (output_values, new_owned_values) = util::clock_pulse(&piso, vec![Value::Off, Value::Off, Value::Off, Value::Off, Value::Off, Value::Off], &owned_values);
+            outputs.push(output_values[0]);
+            owned_values = new_owned_values;
+        }
+
+        assert_eq!(outputs, [Value::Off, Value::On, Value::On, Value::Off]);
+
+        // the serial input follows behind
+        let (output_values, owned_values) = util::clock_pulse(&piso, vec![Value::Off, Value::Off, Value::Off, Value::Off, Value::On, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::Off]);
+        assert_eq!(owned_values, [Value::On, Value::Off, Value::Off, Value::On]);
+    }
+
+    #[test]
+    fn shift_register_universal() {
+        let universal = Function::ShiftRegisterUniversal { length: 3, edge: ClockEdge::Rising };
+        assert_eq!(universal.input_value_count(), 8);
+        assert_eq!(universal.output_value_count(), 3);
+        assert_eq!(universal.owned_value_count(), 4);
+
+        let parallel = [Value::On, Value::Off, Value::Off];
+        let input_values = |mode0, mode1, right_serial_in, left_serial_in| {
+            let mut input_values = parallel.to_vec();
+            input_values.extend([mode0, mode1, right_serial_in, left_serial_in, Value::Off]);
+            input_values
+        };
+
+        // load
+        let (output_values, owned_values) = util::clock_pulse(&universal, input_values(Value::On, Value::On, Value::Off, Value::Off), &[Value::Off; 4]);
+        assert_eq!(output_values, parallel);
+
+        // hold
+        let (output_values, owned_values) = util::clock_pulse(&universal, input_values(Value::Off, Value::Off, Value::On, Value::On), &owned_values);
+        assert_eq!(output_values, parallel);
+
+        // shift towards the highest index
+        let (output_values, owned_values) = util::clock_pulse(&universal, input_values(Value::Off, Value::On, Value::Off, Value::On), &owned_values);
+        assert_eq!(output_values, [Value::On, Value::On, Value::Off]);
+
+        // shift towards index 0
+        let (output_values, owned_values) = util::clock_pulse(&universal, input_values(Value::On, Value::Off, Value::On, Value::Off), &owned_values);
+        assert_eq!(output_values, [Value::On, Value::Off, Value::On]);
+
+        let (output_values, _) = util::clock_pulse(&universal, input_values(Value::On, Value::Off, Value::Off, Value::On), &owned_values);
+        assert_eq!(output_values, [Value::Off, Value::On, Value::Off]);
+    }
+
+    #[test]
+    fn flip_flop_with_options() {
+        let all_options = FlipFlopOptions { enable: true, preset_clear: true, ..FlipFlopOptions::default() };
+
+        let jk = Function::FlipFlop { kind: FlipFlopKind::JK, options: all_options };
+        let d = Function::FlipFlop { kind: FlipFlopKind::D, options: all_options };
+        let t = Function::FlipFlop { kind: FlipFlopKind::T, options: all_options };
+        assert_eq!(jk.input_value_count(), 6);
+        assert_eq!(d.input_value_count(), 5);
+        assert_eq!(t.input_value_count(), 5);
+
+        let plain_d = Function::FlipFlop { kind: FlipFlopKind::D, options: FlipFlopOptions::default() };
+        assert_eq!(plain_d.input_value_count(), 2);
+
+        // without the control pins the flip flops behave like their plain counterparts
+        for (function, plain_function) in [(Function::FlipFlopJK { edge: ClockEdge::Rising }, FlipFlopKind::JK), (Function::FlipFlopD { edge: ClockEdge::Rising }, FlipFlopKind::D), (Function::FlipFlopT { edge: ClockEdge::Rising }, FlipFlopKind::T)] {
+            let flip_flop = Function::FlipFlop { kind: plain_function, options: FlipFlopOptions::default() };
+
+            for input in 0..1 << function.input_value_count() {
+                for owned in 0..4 {
+                    let input_values = to_values(input, function.input_value_count());
+                    let owned_values = to_values(owned, 2);
+                    assert_eq!(flip_flop.evaluate(&input_values, &owned_values), function.evaluate(&input_values, &owned_values));
+                }
+            }
+        }
+
+        let on_off = vec![Value::On,  Value::Off];
+        let off_on = vec![Value::Off, Value::On];
+
+        // inputs: d, enable, preset, clear, clock
+        for state in [Value::On, Value::Off] {
+            for d_input in [Value::On, Value::Off] {
+                for enable in [Value::On, Value::Off] {
+                    for clock in [ClockState::StayOff, ClockState::StayOn, ClockState::TransientToOn, ClockState::TransientToOff] {
+                        let (input_values, owned_values) = dual_input(d_input, state, clock);
+                        let clock = input_values[1];
+
+                        // clear takes precedence over everything
+                        let (output_values, _) = d.evaluate(&[d_input, enable, Value::On, Value::On, clock], &owned_values);
+                        assert_eq!(output_values, off_on);
+                        let (output_values, _) = d.evaluate(&[d_input, enable, Value::Off, Value::On, clock], &owned_values);
+                        assert_eq!(output_values, off_on);
+
+                        // preset comes next
+                        let (output_values, _) = d.evaluate(&[d_input, enable, Value::On, Value::Off, clock], &owned_values);
+                        assert_eq!(output_values, on_off);
+
+                        // a disabled flip flop holds its state on clock edges
+                        let (output_values, new_owned_values) = d.evaluate(&[d_input, Value::Off, Value::Off, Value::Off, clock], &owned_values);
+                        assert_eq!(output_values, &[state, !state]);
+                        assert_eq!(new_owned_values, &[state, clock]);
+                    }
+                }
+            }
+        }
+
+        // an enabled flip flop takes its input on the positive clock edge
+        let (input_values, owned_values) = dual_input(Value::On, Value::Off, ClockState::TransientToOn);
+        let (output_values, _) = d.evaluate(&[input_values[0], Value::On, Value::Off, Value::Off, input_values[1]], &owned_values);
+        assert_eq!(output_values, on_off);
+
+        let (input_values, owned_values) = dual_input(Value::On, Value::Off, ClockState::TransientToOn);
+        let (output_values, _) = t.evaluate(&[input_values[0], Value::On, Value::Off, Value::Off, input_values[1]], &owned_values);
+        assert_eq!(output_values, on_off);
+
+        let (input_values, owned_values) = tripple_input(Value::Off, Value::On, Value::On, ClockState::TransientToOn);
+        let (output_values, _) = jk.evaluate(&[input_values[0], input_values[1], Value::On, Value::Off, Value::Off, input_values[2]], &owned_values);
+        assert_eq!(output_values, off_on);
+
+        // preset and clear without enable
+        let preset_clear = Function::FlipFlop { kind: FlipFlopKind::D, options: FlipFlopOptions { enable: false, preset_clear: true, ..FlipFlopOptions::default() } };
+        let (output_values, _) = preset_clear.evaluate(&[Value::Off, Value::On, Value::Off, Value::Off], &[Value::Off, Value::Off]);
+        assert_eq!(output_values, on_off);
+
+        // enable without preset and clear
+        let enable = Function::FlipFlop { kind: FlipFlopKind::D, options: FlipFlopOptions { enable: true, preset_clear: false, ..FlipFlopOptions::default() } };
+        let (output_values, _) = enable.evaluate(&[Value::On, Value::Off, Value::On], &[Value::Off, Value::Off]);
+        assert_eq!(output_values, off_on);
+        let (output_values, _) = enable.evaluate(&[Value::On, Value::On, Value::On], &[Value::Off, Value::Off]);
+        assert_eq!(output_values, on_off);
+    }
+
+    #[test]
+    fn clock_edges() {
+        let edge_options = |edge| FlipFlopOptions { edge, ..FlipFlopOptions::default() };
+        let rising = Function::FlipFlop { kind: FlipFlopKind::T, options: edge_options(ClockEdge::Rising) };
+        let falling = Function::FlipFlop { kind: FlipFlopKind::T, options: edge_options(ClockEdge::Falling) };
+        let dual = Function::FlipFlop { kind: FlipFlopKind::T, options: edge_options(ClockEdge::Dual) };
+        let plain_falling = Function::FlipFlopT { edge: ClockEdge::Falling };
+        let plain_dual = Function::FlipFlopT { edge: ClockEdge::Dual };
+
+        for (function, toggles_on_rising, toggles_on_falling) in [(&rising, true, false), (&falling, false, true), (&dual, true, true), (&plain_falling, false, true), (&plain_dual, true, true)] {
+            for state in [Value::On, Value::Off] {
+                for clock in [ClockState::StayOff, ClockState::StayOn, ClockState::TransientToOn, ClockState::TransientToOff] {
+                    let toggles = match clock {
+                        ClockState::TransientToOn => toggles_on_rising,
+                        ClockState::TransientToOff => toggles_on_falling,
+                        _ => false,
+                    };
+                    let expected = if toggles { !state } else { state };
+
+                    let (input_values, owned_values) = dual_input(Value::On, state, clock);
+                    let (output_values, _) = function.evaluate(&input_values, &owned_values);
+                    assert_eq!(output_values, [expected, !expected]);
+                }
+            }
+        }
+
+        let register = Function::Register { width: 2, options: edge_options(ClockEdge::Falling) };
+        let (output_values, owned_values) = register.evaluate(&[Value::On, Value::On, Value::On], &[Value::Off; 3]);
+        assert_eq!(output_values, [Value::Off; 2]);
+        let (output_values, _) = register.evaluate(&[Value::On, Value::On, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::On; 2]);
+
+        let d = Function::FlipFlopD { edge: ClockEdge::Falling };
+        let (output_values, owned_values) = d.evaluate(&[Value::On, Value::On], &[Value::Off; 2]);
+        assert_eq!(output_values, [Value::Off, Value::On]);
+        let (output_values, _) = d.evaluate(&[Value::On, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+
+        let jk = Function::FlipFlopJK { edge: ClockEdge::Falling };
+        let (output_values, owned_values) = jk.evaluate(&[Value::On, Value::Off, Value::On], &[Value::Off; 2]);
+        assert_eq!(output_values, [Value::Off, Value::On]);
+        let (output_values, _) = jk.evaluate(&[Value::On, Value::Off, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+
+        // counting up with load, up, enable and reset after the single count bit
+        let counter = Function::Counter { width: 1, modulus: 2, edge: ClockEdge::Falling };
+        let (output_values, owned_values) = counter.evaluate(&[Value::Off, Value::Off, Value::On, Value::On, Value::Off, Value::On], &[Value::Off; 2]);
+        assert_eq!(output_values[0], Value::Off);
+        let (output_values, _) = counter.evaluate(&[Value::Off, Value::Off, Value::On, Value::On, Value::Off, Value::Off], &owned_values);
+        assert_eq!(output_values[0], Value::On);
+
+        let sipo = Function::ShiftRegisterSipo { length: 2, edge: ClockEdge::Falling };
+        let (output_values, owned_values) = sipo.evaluate(&[Value::On, Value::On], &[Value::Off; 3]);
+        assert_eq!(output_values, [Value::Off, Value::Off]);
+        let (output_values, _) = sipo.evaluate(&[Value::On, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+
+        let piso = Function::ShiftRegisterPiso { length: 2, edge: ClockEdge::Dual };
+        let (output_values, owned_values) = piso.evaluate(&[Value::On, Value::Off, Value::On, Value::Off, Value::On], &[Value::Off; 3]);
+        assert_eq!(output_values, [Value::Off]);
+        let (output_values, _) = piso.evaluate(&[Value::On, Value::Off, Value::Off, Value::Off, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::On]);
+
+        let universal = Function::ShiftRegisterUniversal { length: 2, edge: ClockEdge::Falling };
+        let load = |clock| [Value::Off, Value::On, Value::On, Value::On, Value::Off, Value::Off, clock];
+        let (output_values, owned_values) = universal.evaluate(&load(Value::On), &[Value::Off; 3]);
+        assert_eq!(output_values, [Value::Off, Value::Off]);
+        let (output_values, _) = universal.evaluate(&load(Value::Off), &owned_values);
+        assert_eq!(output_values, [Value::Off, Value::On]);
+
+        // writing On to the single address bit 0
+        let ram = Function::Ram { address_width: 1, data_width: 1, edge: ClockEdge::Falling };
+        let (output_values, owned_values) = ram.evaluate(&[Value::Off, Value::On, Value::On, Value::On], &[Value::Off; 3]);
+        assert_eq!(output_values, [Value::Off]);
+        let (output_values, _) = ram.evaluate(&[Value::Off, Value::On, Value::On, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::On]);
+
+        // circuits saved before clock edges were configurable still load
+        let options: FlipFlopOptions = serde_json::from_str(r#"{"enable":true,"preset_clear":false}"#).unwrap();
+        assert_eq!(options.edge, ClockEdge::Rising);
+
+        let counter: Function = serde_json::from_str(r#"{"Counter":{"width":2,"modulus":3}}"#).unwrap();
+        assert!(matches!(counter, Function::Counter { width: 2, modulus: 3, edge: ClockEdge::Rising }));
+        let ram: Function = serde_json::from_str(r#"{"Ram":{"address_width":2,"data_width":4}}"#).unwrap();
+        assert!(matches!(ram, Function::Ram { edge: ClockEdge::Rising, .. }));
+    }
+
+    #[test]
+    fn legacy_flip_flops() {
+        let mut circuit = Circuit::new();
+        let inputs: Vec<_> = (0..2).map(|_| circuit.add_input().1).collect();
+        circuit.add_component(Function::FlipFlopT { edge: ClockEdge::Rising }, inputs).unwrap();
+
+        let mut nested = Circuit::new();
+        let inputs: Vec<_> = (0..3).map(|_| nested.add_input().1).collect();
+        nested.add_component(Function::Circuit(circuit), inputs[..2].to_vec()).unwrap();
+        nested.add_component(Function::FlipFlopJK { edge: ClockEdge::Falling }, inputs.clone()).unwrap();
+        nested.add_component(Function::FlipFlopD { edge: ClockEdge::Rising }, inputs[..2].to_vec()).unwrap();
+
+        let serialized = serde_json::to_string(&nested).unwrap();
+        assert!(serialized.contains(r#"{"FlipFlopJK":{"edge":"Falling"}}"#));
+
+        // saved when the plain flip-flops were unit variants without a clock edge
+        let legacy = serialized.replace(r#"{"FlipFlopT":{"edge":"Rising"}}"#, r#""FlipFlopT""#).replace(r#"{"FlipFlopD":{"edge":"Rising"}}"#, r#""FlipFlopD""#);
+        assert!(legacy.contains(r#""FlipFlopT""#) && legacy.contains(r#""FlipFlopD""#));
+
+        let loaded: Circuit = serde_json::from_str(&legacy).unwrap();
+        match loaded.component(0).function() {
+            Function::Circuit(circuit) => assert!(matches!(circuit.component(0).function(), Function::FlipFlopT { edge: ClockEdge::Rising })),
+            _ => panic!("expected an embedded circuit"),
+        }
+        assert!(matches!(loaded.component(1).function(), Function::FlipFlopJK { edge: ClockEdge::Falling }));
+        assert!(matches!(loaded.component(2).function(), Function::FlipFlopD { edge: ClockEdge::Rising }));
+
+        assert!(serde_json::from_str::<Circuit>(&serialized.replace(r#"{"FlipFlopJK":{"edge":"Falling"}}"#, r#""FlipFlopX""#)).is_err());
+    }
+
+    #[test]
+    fn register() {
+        let register = Function::Register { width: 3, options: FlipFlopOptions { enable: true, preset_clear: true, ..FlipFlopOptions::default() } };
+        assert_eq!(register.input_value_count(), 7);
+        assert_eq!(register.output_value_count(), 3);
+        assert_eq!(register.owned_value_count(), 4);
+
+        let data = [Value::On, Value::Off, Value::On];
+        let input_values = |enable, preset, clear| {
+            let mut input_values = data.to_vec();
+            input_values.extend([enable, preset, clear, Value::Off]);
+            input_values
+        };
+
+        // store on the clock edge
+        let (output_values, owned_values) = util::clock_pulse(&register, input_values(Value::On, Value::Off, Value::Off), &[Value::Off; 4]);
+        assert_eq!(output_values, data);
+        assert_eq!(owned_values, [Value::On, Value::Off, Value::On, Value::On]);
+
+        // hold while disabled
+        let (output_values, _) = util::clock_pulse(&register, input_values(Value::Off, Value::Off, Value::Off), &[Value::Off; 4]);
+        assert_eq!(output_values, [Value::Off; 3]);
+
+        // preset and clear without a clock edge
+        let mut preset_values = input_values(Value::Off, Value::On, Value::Off);
+        preset_values[6] = Value::On;
+        let (output_values, _) = register.evaluate(&preset_values, &owned_values);
+        assert_eq!(output_values, [Value::On; 3]);
+
+        let mut clear_values = input_values(Value::On, Value::On, Value::On);
+        clear_values[6] = Value::On;
+        let (output_values, _) = register.evaluate(&clear_values, &owned_values);
+        assert_eq!(output_values, [Value::Off; 3]);
+    }
+
+    #[test]
+    fn ram() {
+        let ram = Function::Ram { address_width: 2, data_width: 3, edge: ClockEdge::Rising };
+        assert_eq!(ram.input_value_count(), 7);
+        assert_eq!(ram.output_value_count(), 3);
+        assert_eq!(ram.owned_value_count(), 13);
+        assert_eq!(ram.memory_layout(), Some((4, 3)));
+
+        let input_values = |address, data, write_enable| {
+            let mut input_values = to_values(address, 2);
+            input_values.extend(to_values(data, 3));
+            input_values.extend([write_enable, Value::Off]);
+            input_values
+        };
+
+        // write a different word to every address
+        let mut owned_values = vec![Value::Off; 13];
+        for address in 0..4 {
+            let (output_values, new_owned_values) = util::clock_pulse(&ram, input_values(address, address + 3, Value::On), &owned_values);
+            assert_eq!(output_values, to_values(address + 3, 3));
+            owned_values = new_owned_values;
+        }
+
+        // the data out follows the address without a clock
+        for address in 0..4 {
+            let (output_values, _) = ram.evaluate(&input_values(address, 0, Value::On), &owned_values);
+            assert_eq!(output_values, to_values(address + 3, 3));
+        }
+
+        // nothing is written without write enable
+        let (output_values, new_owned_values) = util::clock_pulse(&ram, input_values(1, 0, Value::Off), &owned_values);
+        assert_eq!(output_values, to_values(4, 3));
+        assert_eq!(new_owned_values, owned_values);
+
+        // or without a positive clock edge
+        let (output_values, _) = ram.evaluate(&input_values(1, 0, Value::On), &owned_values);
+        assert_eq!(output_values, to_values(4, 3));
+    }
+
+    #[test]
+    fn rom() {
+        let mut image = RomImage::new("unused".into(), crate::memory::MemoryFormat::Hex);
+        image.set_words(vec![0x3, 0x5, 0x6]);
+
+        let rom = Function::Rom { address_width: 2, data_width: 3, image };
+        assert_eq!(rom.input_value_count(), 2);
+        assert_eq!(rom.output_value_count(), 3);
+        assert_eq!(rom.owned_value_count(), 0);
+
+        assert_eq!(rom.evaluate(&to_values(0, 2), &[]), (to_values(0x3, 3), vec![]));
+        assert_eq!(rom.evaluate(&to_values(1, 2), &[]), (to_values(0x5, 3), vec![]));
+        assert_eq!(rom.evaluate(&to_values(2, 2), &[]), (to_values(0x6, 3), vec![]));
+
+        // addresses beyond the image read 0
+        assert_eq!(rom.evaluate(&to_values(3, 2), &[]), (to_values(0x0, 3), vec![]));
+    }
+
+    #[test]
+    fn comparator() {
+        let width = 3;
+        let unsigned = Function::Comparator { width, signed: false };
+        let signed = Function::Comparator { width, signed: true };
+        assert_eq!(unsigned.input_value_count(), 9);
+        assert_eq!(unsigned.output_value_count(), 3);
+
+        let less =    vec![Value::On,  Value::Off, Value::Off];
+        let equal =   vec![Value::Off, Value::On,  Value::Off];
+        let greater = vec![Value::Off, Value::Off, Value::On];
+
+        // with A=B as cascade input every combination of A and B
+        for a in 0..8 {
+            for b in 0..8 {
+                let mut input_values = to_values(a, width);
+                input_values.extend(to_values(b, width));
+                input_values.extend(equal.iter());
+
+                let expected = match a.cmp(&b) {
+                    Ordering::Less => &less,
+                    Ordering::Equal => &equal,
+                    Ordering::Greater => &greater,
+                };
+                assert_eq!(&unsigned.evaluate(&input_values, &[]).0, expected, "unsigned a: {a}, b: {b}");
+
+                let expected = match to_signed(a, width).cmp(&to_signed(b, width)) {
+                    Ordering::Less => &less,
+                    Ordering::Equal => &equal,
+                    Ordering::Greater => &greater,
+                };
+                assert_eq!(&signed.evaluate(&input_values, &[]).0, expected, "signed a: {a}, b: {b}");
+
+                // the cascade inputs only matter if A and B are equal
+                for cascade in 0..8 {
+                    let mut input_values = to_values(a, width);
+                    input_values.extend(to_values(b, width));
+                    input_values.extend(to_values(cascade, 3));
+
+                    if a != b {
+                        assert_eq!(&unsigned.evaluate(&input_values, &[]).0, if a < b { &less } else { &greater });
+                    }
+                }
+            }
+        }
+
+        // cascade inputs as on the 7485 for equal A and B: A<B, A=B, A>B
+        let cascade_table = [
+            ([Value::Off, Value::Off, Value::On],  [Value::Off, Value::Off, Value::On]),
+            ([Value::On,  Value::Off, Value::Off], [Value::On,  Value::Off, Value::Off]),
+            ([Value::Off, Value::On,  Value::Off], [Value::Off, Value::On,  Value::Off]),
+            ([Value::On,  Value::On,  Value::On],  [Value::Off, Value::On,  Value::Off]),
+            ([Value::Off, Value::On,  Value::On],  [Value::Off, Value::On,  Value::Off]),
+            ([Value::On,  Value::On,  Value::Off], [Value::Off, Value::On,  Value::Off]),
+            ([Value::On,  Value::Off, Value::On],  [Value::Off, Value::Off, Value::Off]),
+            ([Value::Off, Value::Off, Value::Off], [Value::On,  Value::Off, Value::On]),
+        ];
+
+        for (cascade, expected) in cascade_table {
+            let mut input_values = to_values(5, width);
+            input_values.extend(to_values(5, width));
+            input_values.extend(cascade);
+            assert_eq!(unsigned.evaluate(&input_values, &[]), (expected.to_vec(), vec![]));
+            assert_eq!(signed.evaluate(&input_values, &[]), (expected.to_vec(), vec![]));
+        }
+
+        // two cascaded 3 bit comparators match a 6 bit comparator
+        let wide = Function::Comparator { width: 6, signed: true };
+        for a in 0..64 {
+            for b in 0..64 {
+                let mut low_input_values = to_values(a & 7, width);
+                low_input_values.extend(to_values(b & 7, width));
+                low_input_values.extend(equal.iter());
+                let (low_values, _) = unsigned.evaluate(&low_input_values, &[]);
+
+                let mut high_input_values = to_values(a >> 3, width);
+                high_input_values.extend(to_values(b >> 3, width));
+                high_input_values.extend(low_values);
+
+                let mut wide_input_values = to_values(a, 6);
+                wide_input_values.extend(to_values(b, 6));
+                wide_input_values.extend(equal.iter());
+
+                assert_eq!(signed.evaluate(&high_input_values, &[]), wide.evaluate(&wide_input_values, &[]));
+            }
+        }
+    }
+
+    #[test]
+    fn tri_state_buffer() {
+        let buffer = Function::TriStateBuffer { width: 2 };
+        assert_eq!(buffer.input_value_count(), 3);
+        assert_eq!(buffer.output_value_count(), 2);
+        assert_eq!(buffer.owned_value_count(), 3);
+        assert!(buffer.is_bus_driver());
+        assert!(!Function::And.is_bus_driver());
+
+        let (output_values, owned_values) = buffer.evaluate(&[Value::On, Value::Off, Value::On], &[Value::Off; 3]);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+        assert_eq!(buffer.driven_values(&owned_values), Some(vec![Some(Value::On), Some(Value::Off)]));
+
+        let (output_values, owned_values) = buffer.evaluate(&[Value::On, Value::Off, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::Off, Value::Off]);
+        assert_eq!(buffer.driven_values(&owned_values), Some(vec![None, None]));
+
+        assert_eq!(Function::And.driven_values(&[]), None);
+    }
+
+    #[test]
+    fn open_drain_and_open_source() {
+        let open_drain = Function::OpenDrain { width: 2 };
+        let open_source = Function::OpenSource { width: 2 };
+        assert_eq!(open_drain.input_value_count(), 2);
+        assert_eq!(open_drain.output_value_count(), 2);
+        assert_eq!(open_drain.owned_value_count(), 2);
+        assert!(open_drain.is_bus_driver());
+        assert!(open_source.is_bus_driver());
+
+        let (_, owned_values) = open_drain.evaluate(&[Value::On, Value::Off], &[Value::Off; 2]);
+        assert_eq!(open_drain.driven_values(&owned_values), Some(vec![None, Some(Value::Off)]));
+
+        let (_, owned_values) = open_source.evaluate(&[Value::On, Value::Off], &[Value::Off; 2]);
+        assert_eq!(open_source.driven_values(&owned_values), Some(vec![Some(Value::On), None]));
+    }
+
+    #[test]
+    fn latch_d() {
+        let latch = Function::LatchD;
+
+        // transparent while enabled
+        let (output_values, owned_values) = latch.evaluate(&[Value::On, Value::On], &[Value::Off]);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+        assert_eq!(owned_values, [Value::On]);
+
+        let (output_values, owned_values) = latch.evaluate(&[Value::Off, Value::On], &owned_values);
+        assert_eq!(output_values, [Value::Off, Value::On]);
+        assert_eq!(owned_values, [Value::Off]);
+
+        // holds while disabled
+        let (output_values, owned_values) = latch.evaluate(&[Value::On, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::Off, Value::On]);
+        assert_eq!(owned_values, [Value::Off]);
+
+        let (output_values, _) = latch.evaluate(&[Value::Off, Value::Off], &[Value::On]);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+    }
+
+    #[test]
+    fn latch_sr() {
+        let latch = Function::LatchSR;
+
+        let (output_values, owned_values) = latch.evaluate(&[Value::On, Value::Off, Value::On], &[Value::Off]);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+        assert_eq!(owned_values, [Value::On]);
+
+        let (output_values, owned_values) = latch.evaluate(&[Value::Off, Value::Off, Value::On], &owned_values);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+        assert_eq!(owned_values, [Value::On]);
+
+        // set and reset are ignored while disabled
+        let (output_values, owned_values) = latch.evaluate(&[Value::Off, Value::On, Value::Off], &owned_values);
+        assert_eq!(output_values, [Value::On, Value::Off]);
+        assert_eq!(owned_values, [Value::On]);
+
+        let (output_values, owned_values) = latch.evaluate(&[Value::Off, Value::On, Value::On], &owned_values);
+        assert_eq!(output_values, [Value::Off, Value::On]);
+        assert_eq!(owned_values, [Value::Off]);
+
+        // the forbidden state turns both outputs off like the RS flip-flop
+        let (output_values, owned_values) = latch.evaluate(&[Value::On, Value::On, Value::On], &owned_values);
+        assert_eq!(output_values, [Value::Off, Value::Off]);
+        assert_eq!(owned_values, [Value::Off]);
+    }
+
     mod util {
         use super::super::*;
 
@@ -511,7 +2346,7 @@ mod tests {
             let mut circuit = Circuit::new();
             let (_, value0_index) = circuit.add_input();
             let (_, value1_index) = circuit.add_input();
-            let (_, value2_index) = circuit.add_component(Function::And, vec![value0_index, value1_index]);
+            let (_, value2_index) = circuit.add_component(Function::And, vec![value0_index, value1_index]).unwrap();
             let _ = circuit.add_output(value2_index[0]);
 
             circuit
@@ -524,6 +2359,43 @@ mod tests {
             TransientToOff,
         }
 
+        /// Evaluates the function with its last input, the clock, `Off` and then `On` and returns the final result.
+        pub(super) fn clock_pulse(function: &Function, mut input_values: Vec<Value>, owned_values: &[Value]) -> (Vec<Value>, Vec<Value>) {
+            let clock_index = input_values.len() - 1;
+
+            input_values[clock_index] = Value::Off;
+            let (_, owned_values) = function.evaluate(&input_values, owned_values);
+
+            input_values[clock_index] = Value::On;
+            function.evaluate(&input_values, &owned_values)
+        }
+
+        pub(super) enum CounterControl {
+            Up,
+            Down,
+            Load,
+            Disabled,
+            UpWhileReset,
+        }
+
+        pub(super) fn counter_pulse(counter: &Function, data: usize, control: CounterControl, owned_values: &[Value]) -> (Vec<Value>, Vec<Value>) {
+            let Function::Counter { width, .. } = counter else {
+                panic!("expected a counter");
+            };
+
+            let (load, up, enable, reset) = match control {
+                CounterControl::Up => (Value::Off, Value::On, Value::On, Value::Off),
+                CounterControl::Down => (Value::Off, Value::Off, Value::On, Value::Off),
+                CounterControl::Load => (Value::On, Value::On, Value::On, Value::Off),
+                CounterControl::Disabled => (Value::Off, Value::On, Value::Off, Value::Off),
+                CounterControl::UpWhileReset => (Value::Off, Value::On, Value::On, Value::On),
+            };
+
+            let mut input_values = to_values(data, *width);
+            input_values.extend([load, up, enable, reset, Value::Off]);
+            clock_pulse(counter, input_values, owned_values)
+        }
+
         pub(super) fn dual_input(i: Value, state: Value, clock: ClockState) -> (Vec<Value>, Vec<Value>) {
             let input_values = vec![i, match clock {
                 ClockState::StayOff => Value::Off,
@@ -1,22 +1,40 @@
 use std::{ops::{BitAnd, BitOr, Not, BitXor}, fmt::Display};
 
-use element::{Input, Output, Component};
-use function::Function;
+use element::{Input, Output, Component, Bus, BusResolution};
+use function::{Function, FunctionError};
 use serde::{Deserialize, Serialize};
 
 pub mod function;
 pub mod element;
+pub mod library;
+pub mod memory;
 pub mod simulator;
+pub mod state_machine;
+pub mod truth_table;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Circuit {
     inputs: Vec<Input>,
     outputs: Vec<Output>,
     components: Vec<Component>,
+    #[serde(default)]
+    buses: Vec<Bus>,
     value_list_len: usize,
     owned_value_list_len: usize,
 }
 
+/// Why a change to a circuit was refused.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CircuitError {
+    NotABusDriver,
+    NotABus(usize),
+    BusCount { expected: usize, actual: usize },
+    UnknownComponent(usize),
+    InitialStateLength { owned: usize, actual: usize },
+    InvalidFunction(FunctionError),
+    InvalidComponent { component_index: usize, error: FunctionError },
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Value {
     On,
@@ -29,6 +47,7 @@ impl Circuit {
             inputs: Vec::new(),
             outputs: Vec::new(),
             components: Vec::new(),
+            buses: Vec::new(),
             value_list_len: 0,
             owned_value_list_len: 0,
         }
@@ -46,25 +65,82 @@ impl Circuit {
 
     pub fn add_output(&mut self, value_index: usize) -> usize {
         self.outputs.push(Output::new(value_index));
-        let output_index = self.outputs.len() - 1;
+        self.outputs.len() - 1
+    }
 
-        output_index
+    pub fn add_component(&mut self, function: Function, input_value_indices: Vec<usize>) -> Result<(usize, Vec<usize>), CircuitError> {
+        function.validate().map_err(CircuitError::InvalidFunction)?;
+        Ok(self.add_component_unchecked(function, input_value_indices))
     }
 
-    pub fn add_component(&mut self, function: Function, input_value_indices: Vec<usize>) -> (usize, Vec<usize>) {
+    /// Adds a component whose function is known to be valid, like a gate.
+    pub(crate) fn add_component_unchecked(&mut self, function: Function, input_value_indices: Vec<usize>) -> (usize, Vec<usize>) {
         let output_value_start_index = self.value_list_len;
         self.value_list_len += function.output_value_count();
         let output_value_indices: Vec<usize> = (output_value_start_index..self.value_list_len).collect();
 
+        if function.is_bus_driver() {
+            let (resolution, pull) = match function {
+                Function::OpenDrain { .. } => (BusResolution::WiredAnd, Some(Value::On)),
+                Function::OpenSource { .. } => (BusResolution::WiredOr, Some(Value::Off)),
+                _ => (BusResolution::TriState, None),
+            };
+
+            self.buses.extend(output_value_indices.iter().map(|&value_index| Bus::new(value_index, resolution, pull)));
+        }
+
+        let component_index = self.push_component(function, input_value_indices, output_value_indices.clone());
+
+        (component_index, output_value_indices)
+    }
+
+    /// Adds buses that are not driven by any component yet.
+    pub fn add_bus(&mut self, width: usize, resolution: BusResolution, pull: Option<Value>) -> Vec<usize> {
+        let bus_value_start_index = self.value_list_len;
+        self.value_list_len += width;
+        let bus_value_indices: Vec<usize> = (bus_value_start_index..self.value_list_len).collect();
+
+        self.buses.extend(bus_value_indices.iter().map(|&value_index| Bus::new(value_index, resolution, pull)));
+
+        bus_value_indices
+    }
+
+    /// Changes how the drivers of a bus are combined and its pull resistor.
+    pub fn set_bus_resolution(&mut self, value_index: usize, resolution: BusResolution, pull: Option<Value>) -> Result<(), CircuitError> {
+        let bus = self.buses.iter_mut().find(|bus| bus.value_index() == value_index).ok_or(CircuitError::NotABus(value_index))?;
+        bus.set_resolution(resolution, pull);
+        Ok(())
+    }
+
+    /// Adds a bus driving component whose outputs drive existing buses, for
+    /// example ones created by `add_bus` or by another bus driving component.
+    pub fn add_bus_driver(&mut self, function: Function, input_value_indices: Vec<usize>, bus_value_indices: Vec<usize>) -> Result<usize, CircuitError> {
+        function.validate().map_err(CircuitError::InvalidFunction)?;
+
+        if !function.is_bus_driver() {
+            return Err(CircuitError::NotABusDriver);
+        }
+
+        if function.output_value_count() != bus_value_indices.len() {
+            return Err(CircuitError::BusCount { expected: function.output_value_count(), actual: bus_value_indices.len() });
+        }
+
+        if let Some(&value_index) = bus_value_indices.iter().find(|&&value_index| !self.is_bus(value_index)) {
+            return Err(CircuitError::NotABus(value_index));
+        }
+
+        Ok(self.push_component(function, input_value_indices, bus_value_indices))
+    }
+
+    fn push_component(&mut self, function: Function, input_value_indices: Vec<usize>, output_value_indices: Vec<usize>) -> usize {
         let owned_value_start_index = self.owned_value_list_len;
         self.owned_value_list_len += function.owned_value_count();
         let owned_value_indices: Vec<usize> = (owned_value_start_index..self.owned_value_list_len).collect();
 
-        let component = Component::new(function, input_value_indices, output_value_indices.clone(), owned_value_indices);
+        let component = Component::new(function, input_value_indices, output_value_indices, owned_value_indices);
         self.components.push(component);
-        let component_index = self.components.len() - 1;
 
-        (component_index, output_value_indices)
+        self.components.len() - 1
     }
 
     pub fn input(&self, input_index: usize) -> &Input {
@@ -91,6 +167,59 @@ impl Circuit {
         &self.components
     }
 
+    pub fn all_buses(&self) -> &[Bus] {
+        &self.buses
+    }
+
+    pub fn bus(&self, value_index: usize) -> Option<&Bus> {
+        self.buses.iter().find(|bus| bus.value_index() == value_index)
+    }
+
+    pub fn is_bus(&self, value_index: usize) -> bool {
+        self.bus(value_index).is_some()
+    }
+
+    pub(crate) fn all_components_mut(&mut self) -> &mut [Component] {
+        &mut self.components
+    }
+
+    /// Sets the values the component starts with when a simulation starts or is
+    /// reset, for example `[Value::On]` for a flip-flop that starts `On`.
+    pub fn set_initial_state(&mut self, component_index: usize, initial_state: Vec<Value>) -> Result<(), CircuitError> {
+        let component = self.components.get_mut(component_index).ok_or(CircuitError::UnknownComponent(component_index))?;
+
+        if initial_state.len() > component.owned_value_indices().len() {
+            return Err(CircuitError::InitialStateLength { owned: component.owned_value_indices().len(), actual: initial_state.len() });
+        }
+
+        component.set_initial_state(initial_state);
+        Ok(())
+    }
+
+    /// Connects an input of a component to another value, which allows feedback
+    /// from components that are added later.
+    pub(crate) fn set_component_input(&mut self, component_index: usize, input: usize, value_index: usize) {
+        self.components[component_index].set_input_value_index(input, value_index);
+    }
+
+    /// Checks that every component can be simulated, which is needed for
+    /// circuits that were deserialized instead of built with `add_component`.
+    pub fn validate(&self) -> Result<(), CircuitError> {
+        for (component_index, component) in self.components.iter().enumerate() {
+            component.function().validate().map_err(|error| CircuitError::InvalidComponent { component_index, error })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn has_unresolved_subcircuits(&self) -> bool {
+        self.components.iter().any(|component| match component.function() {
+            Function::Subcircuit(reference) => !reference.is_resolved(),
+            Function::Circuit(circuit) => circuit.has_unresolved_subcircuits(),
+            _ => false,
+        })
+    }
+
     pub fn value_list_len(&self) -> usize {
         self.value_list_len
     }
@@ -100,6 +229,12 @@ impl Circuit {
     }
 }
 
+impl Default for Circuit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BitAnd for Value {
     type Output = Self;
 
@@ -149,4 +284,20 @@ impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{self:?}")
     }
-}
\ No newline at end of file
+}
+
+impl Display for CircuitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitError::NotABusDriver => write!(f, "only bus driving components can drive a bus"),
+            CircuitError::NotABus(value_index) => write!(f, "value {value_index} is not a bus"),
+            CircuitError::BusCount { expected, actual } => write!(f, "expected {expected} buses to drive but got {actual}"),
+            CircuitError::UnknownComponent(component_index) => write!(f, "there is no component {component_index}"),
+            CircuitError::InitialStateLength { owned, actual } => write!(f, "the component stores {owned} values but the initial state has {actual}"),
+            CircuitError::InvalidFunction(error) => write!(f, "{error}"),
+            CircuitError::InvalidComponent { component_index, error } => write!(f, "component {component_index}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for CircuitError {}
@@ -0,0 +1,353 @@
+use std::{collections::{BTreeMap, HashMap}, fmt::Display, fs, path::Path, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Circuit, function::Function, memory::{self, MemoryError}};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Library {
+    definitions: BTreeMap<String, BTreeMap<u32, Circuit>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SubcircuitReference {
+    name: String,
+    version: u32,
+    #[serde(skip)]
+    circuit: Option<Arc<Circuit>>,
+}
+
+#[derive(Debug)]
+pub enum LibraryError {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+    Memory(MemoryError),
+    DuplicateDefinition { name: String, version: u32 },
+    MissingDefinition { name: String, version: u32 },
+    RecursiveDefinition(Vec<(String, u32)>),
+}
+
+impl Library {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every given library file into one library so that definitions
+    /// may reference definitions stored in any of the other files.
+    pub fn load_files<P: AsRef<Path>>(paths: &[P]) -> Result<Self, LibraryError> {
+        let mut library = Self::new();
+
+        for path in paths {
+            library.load_file(path)?;
+        }
+
+        Ok(library)
+    }
+
+    /// Adds the definitions of the library file and loads the images of their
+    /// ROMs, whose relative paths start at the directory of the library file.
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), LibraryError> {
+        let serial_library = fs::read(&path).map_err(LibraryError::Io)?;
+        let mut loaded_library: Library = serde_json::from_slice(&serial_library).map_err(LibraryError::Serialization)?;
+
+        let base_directory = path.as_ref().parent().unwrap_or(Path::new(""));
+        for circuit in loaded_library.definitions.values_mut().flat_map(BTreeMap::values_mut) {
+            memory::load_roms(circuit, base_directory).map_err(LibraryError::Memory)?;
+        }
+
+        self.merge(loaded_library)
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<(), LibraryError> {
+        let serialized_library = serde_json::to_string_pretty(self).map_err(LibraryError::Serialization)?;
+        fs::write(path, serialized_library).map_err(LibraryError::Io)
+    }
+
+    pub fn merge(&mut self, other: Library) -> Result<(), LibraryError> {
+        for (name, versions) in other.definitions {
+            for (version, circuit) in versions {
+                self.add(name.clone(), version, circuit)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn add(&mut self, name: String, version: u32, circuit: Circuit) -> Result<(), LibraryError> {
+        let versions = self.definitions.entry(name.clone()).or_default();
+
+        if versions.contains_key(&version) {
+            return Err(LibraryError::DuplicateDefinition { name, version });
+        }
+
+        versions.insert(version, circuit);
+        Ok(())
+    }
+
+    pub fn definition(&self, name: &str, version: u32) -> Option<&Circuit> {
+        self.definitions.get(name).and_then(|versions| versions.get(&version))
+    }
+
+    pub fn all_definitions(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.definitions.iter()
+            .flat_map(|(name, versions)| versions.keys().map(move |&version| (name.as_str(), version)))
+    }
+
+    /// Creates a resolved `Function::Subcircuit` for the definition that can be
+    /// used with `Circuit::add_component`.
+    pub fn instantiate(&self, name: &str, version: u32) -> Result<Function, LibraryError> {
+        let mut function = Function::Subcircuit(SubcircuitReference::new(name.to_owned(), version));
+        self.resolve_function(&mut function, &mut HashMap::new(), &mut Vec::new())?;
+
+        Ok(function)
+    }
+
+    /// Resolves all subcircuit references of the circuit, including the ones
+    /// nested inside other subcircuits or embedded circuits.
+    pub fn resolve(&self, circuit: &mut Circuit) -> Result<(), LibraryError> {
+        self.resolve_circuit(circuit, &mut HashMap::new(), &mut Vec::new())
+    }
+
+    /// Checks that every definition can be resolved and none of them contains itself.
+    pub fn validate(&self) -> Result<(), LibraryError> {
+        let mut resolved = HashMap::new();
+
+        for (name, version) in self.all_definitions() {
+            self.resolve_definition(name, version, &mut resolved, &mut Vec::new())?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve_circuit(&self, circuit: &mut Circuit, resolved: &mut HashMap<(String, u32), Arc<Circuit>>, visiting: &mut Vec<(String, u32)>) -> Result<(), LibraryError> {
+        for component in circuit.all_components_mut() {
+            self.resolve_function(component.function_mut(), resolved, visiting)?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve_function(&self, function: &mut Function, resolved: &mut HashMap<(String, u32), Arc<Circuit>>, visiting: &mut Vec<(String, u32)>) -> Result<(), LibraryError> {
+        match function {
+            Function::Subcircuit(reference) => {
+                let circuit = self.resolve_definition(&reference.name, reference.version, resolved, visiting)?;
+                reference.circuit = Some(circuit);
+                Ok(())
+            },
+            Function::Circuit(circuit) => self.resolve_circuit(circuit, resolved, visiting),
+            _ => Ok(()),
+        }
+    }
+
+    fn resolve_definition(&self, name: &str, version: u32, resolved: &mut HashMap<(String, u32), Arc<Circuit>>, visiting: &mut Vec<(String, u32)>) -> Result<Arc<Circuit>, LibraryError> {
+        let key = (name.to_owned(), version);
+
+        if let Some(circuit) = resolved.get(&key) {
+            return Ok(circuit.clone());
+        }
+
+        if let Some(position) = visiting.iter().position(|visited| visited == &key) {
+            let mut cycle = visiting[position..].to_vec();
+            cycle.push(key);
+            return Err(LibraryError::RecursiveDefinition(cycle));
+        }
+
+        let mut circuit = self.definition(name, version)
+            .ok_or_else(|| LibraryError::MissingDefinition { name: name.to_owned(), version })?
+            .clone();
+
+        visiting.push(key.clone());
+        self.resolve_circuit(&mut circuit, resolved, visiting)?;
+        visiting.pop();
+
+        let circuit = Arc::new(circuit);
+        resolved.insert(key, circuit.clone());
+
+        Ok(circuit)
+    }
+}
+
+impl SubcircuitReference {
+    pub fn new(name: String, version: u32) -> Self {
+        Self {
+            name,
+            version,
+            circuit: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn circuit(&self) -> Option<&Circuit> {
+        self.circuit.as_deref()
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.circuit.is_some()
+    }
+}
+
+impl Display for LibraryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryError::Io(error) => write!(f, "io error: {error}"),
+            LibraryError::Serialization(error) => write!(f, "serialization error: {error}"),
+            LibraryError::Memory(error) => write!(f, "error while loading ROM images: {error}"),
+            LibraryError::DuplicateDefinition { name, version } => write!(f, "{name}@{version} is defined more than once"),
+            LibraryError::MissingDefinition { name, version } => write!(f, "{name}@{version} is not defined"),
+            LibraryError::RecursiveDefinition(cycle) => {
+                let cycle = cycle.iter().map(|(name, version)| format!("{name}@{version}")).collect::<Vec<_>>().join(" -> ");
+                write!(f, "recursive definition: {cycle}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CircuitError, Value};
+    use crate::function::FunctionError;
+    use crate::simulator::Simulator;
+
+    use super::*;
+
+    #[test]
+    fn instantiate_resolves_nested_references() {
+        let mut library = Library::new();
+        library.add("and".into(), 1, util::generate_and_circuit()).unwrap();
+        library.add("and3".into(), 1, util::generate_and3_circuit()).unwrap();
+
+        let and3 = library.instantiate("and3", 1).unwrap();
+        assert_eq!(and3.input_value_count(), 3);
+        assert_eq!(and3.output_value_count(), 1);
+
+        assert_eq!(and3.evaluate(&[Value::On,  Value::On, Value::On],  &[]), (vec![Value::On],  vec![]));
+        assert_eq!(and3.evaluate(&[Value::On,  Value::On, Value::Off], &[]), (vec![Value::Off], vec![]));
+        assert_eq!(and3.evaluate(&[Value::Off, Value::On, Value::On],  &[]), (vec![Value::Off], vec![]));
+    }
+
+    #[test]
+    fn references_survive_serialization() {
+        let mut library = Library::new();
+        library.add("and".into(), 1, util::generate_and_circuit()).unwrap();
+        library.add("and3".into(), 1, util::generate_and3_circuit()).unwrap();
+
+        let mut circuit = Circuit::new();
+        let (_, a) = circuit.add_input();
+        let (_, b) = circuit.add_input();
+        let (_, c) = circuit.add_input();
+        let (_, outputs) = circuit.add_component(library.instantiate("and3", 1).unwrap(), vec![a, b, c]).unwrap();
+        circuit.add_output(outputs[0]);
+
+        let serialized = serde_json::to_string(&circuit).unwrap();
+        assert!(!serialized.contains("\"Circuit\""));
+
+        let mut loaded: Circuit = serde_json::from_str(&serialized).unwrap();
+        match loaded.component(0).function() {
+            Function::Subcircuit(reference) => assert!(!reference.is_resolved()),
+            _ => panic!("expected a subcircuit"),
+        }
+
+        assert!(matches!(
+            Simulator::new(loaded.clone()),
+            Err(CircuitError::InvalidComponent { component_index: 0, error: FunctionError::UnresolvedSubcircuit { name, version: 1 } }) if name == "and3"
+        ));
+        assert!(matches!(
+            loaded.clone().add_component(loaded.component(0).function().clone(), vec![a, b, c]),
+            Err(CircuitError::InvalidFunction(FunctionError::UnresolvedSubcircuit { .. }))
+        ));
+
+        library.resolve(&mut loaded).unwrap();
+        assert!(Simulator::new(loaded.clone()).is_ok());
+        let function = Function::Circuit(loaded);
+        assert_eq!(function.evaluate(&[Value::On, Value::On, Value::On], &[]), (vec![Value::On], vec![]));
+    }
+
+    #[test]
+    fn missing_definition() {
+        let mut library = Library::new();
+        library.add("and3".into(), 1, util::generate_and3_circuit()).unwrap();
+
+        assert!(matches!(library.instantiate("and3", 2), Err(LibraryError::MissingDefinition { .. })));
+        assert!(matches!(library.instantiate("and3", 1), Err(LibraryError::MissingDefinition { name, version: 1 }) if name == "and"));
+    }
+
+    #[test]
+    fn duplicate_definition() {
+        let mut library = Library::new();
+        library.add("and".into(), 1, util::generate_and_circuit()).unwrap();
+
+        assert!(matches!(library.add("and".into(), 1, util::generate_and_circuit()), Err(LibraryError::DuplicateDefinition { .. })));
+        assert!(library.add("and".into(), 2, util::generate_and_circuit()).is_ok());
+    }
+
+    #[test]
+    fn recursive_definition() {
+        let mut plain_library = Library::new();
+        plain_library.add("a".into(), 1, util::generate_and_circuit()).unwrap();
+        plain_library.add("b".into(), 1, util::generate_and_circuit()).unwrap();
+        plain_library.add("c".into(), 1, util::generate_and_circuit()).unwrap();
+
+        let mut library = Library::new();
+        library.add("a".into(), 1, util::generate_wrapper_circuit(&plain_library, "b")).unwrap();
+        library.add("b".into(), 1, util::generate_wrapper_circuit(&plain_library, "a")).unwrap();
+        library.add("c".into(), 1, util::generate_wrapper_circuit(&plain_library, "c")).unwrap();
+
+        match library.instantiate("a", 1) {
+            Err(LibraryError::RecursiveDefinition(cycle)) => {
+                assert_eq!(cycle, vec![("a".to_owned(), 1), ("b".to_owned(), 1), ("a".to_owned(), 1)]);
+            },
+            _ => panic!("expected a recursive definition"),
+        }
+
+        assert!(matches!(library.instantiate("c", 1), Err(LibraryError::RecursiveDefinition(_))));
+        assert!(matches!(library.validate(), Err(LibraryError::RecursiveDefinition(_))));
+    }
+
+    mod util {
+        use super::super::*;
+
+        pub(super) fn generate_and_circuit() -> Circuit {
+            let mut circuit = Circuit::new();
+            let (_, value0_index) = circuit.add_input();
+            let (_, value1_index) = circuit.add_input();
+            let (_, value2_index) = circuit.add_component(Function::And, vec![value0_index, value1_index]).unwrap();
+            let _ = circuit.add_output(value2_index[0]);
+
+            circuit
+        }
+
+        pub(super) fn generate_and3_circuit() -> Circuit {
+            let mut library = Library::new();
+            library.add("and".into(), 1, generate_and_circuit()).unwrap();
+
+            let mut circuit = Circuit::new();
+            let (_, value0_index) = circuit.add_input();
+            let (_, value1_index) = circuit.add_input();
+            let (_, value2_index) = circuit.add_input();
+            let (_, value3_index) = circuit.add_component(library.instantiate("and", 1).unwrap(), vec![value0_index, value1_index]).unwrap();
+            let (_, value4_index) = circuit.add_component(library.instantiate("and", 1).unwrap(), vec![value3_index[0], value2_index]).unwrap();
+            let _ = circuit.add_output(value4_index[0]);
+
+            circuit
+        }
+
+        pub(super) fn generate_wrapper_circuit(library: &Library, name: &str) -> Circuit {
+            let mut circuit = Circuit::new();
+            let (_, value0_index) = circuit.add_input();
+            let (_, value1_index) = circuit.add_input();
+            let (_, value2_index) = circuit.add_component(library.instantiate(name, 1).unwrap(), vec![value0_index, value1_index]).unwrap();
+            let _ = circuit.add_output(value2_index[0]);
+
+            circuit
+        }
+    }
+}
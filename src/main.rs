@@ -1,7 +1,7 @@
-use std::{process::exit, fs, io::Write};
+use std::{process::exit, fs, io::Write, num::NonZeroUsize, path::Path};
 
-use inquire::{Select, MultiSelect, list_option::ListOption, validator::Validation, Text};
-use simulator::{function::Function, Value, simulator::Simulator, Circuit};
+use inquire::{Select, MultiSelect, list_option::ListOption, validator::Validation, Text, CustomType, Confirm};
+use simulator::{function::{Function, FlipFlopKind, FlipFlopOptions, ClockEdge}, memory::{self, MemoryFormat, RomImage}, Value, simulator::{Simulator, Oscillation, Interruption, Breakpoint, Explanation, CauseSource, SimulatorConfig, InitialValues, EventOrder, TraceOptions, TraceEvent, Session}, Circuit, library::{Library, SubcircuitReference}, element::BusResolution, truth_table::TruthTable, state_machine::{StateMachine, MachineKind, StateEncoding}};
 
 mod cli_util;
 
@@ -9,6 +9,10 @@ const INPUT: &str = "Input";
 const OUTPUT: &str = "Output";
 const COMPONENT: &str = "Component";
 const VALUE: &str = "Value";
+const MEMORY: &str = "Memory";
+const BUS: &str = "Bus";
+const TRACE: &str = "Trace";
+const CAUSE: &str = "Cause";
 
 const ALL: &str = "All";
 const BY_INDEX: &str = "By index";
@@ -26,6 +30,7 @@ fn initialize() -> Simulator {
     let options = vec![
         "New",
         "Load",
+        "Load session",
     ];
 
     let answer = Select::new("Do you want to create a new circuit simulation or load an existing?", options).prompt();
@@ -35,12 +40,16 @@ fn initialize() -> Simulator {
             match choice {
                 "New" => {
                     println!("Creating new empty simulator simulation!");
-                    Simulator::new(Circuit::new())
+                    empty_simulator(SimulatorConfig::default())
                 },
                 "Load" => {
                     let loaded_circuit = load();
-                    Simulator::new(loaded_circuit)
+                    Simulator::new(loaded_circuit).unwrap_or_else(|error| {
+                        println!("The circuit cannot be simulated: {error}");
+                        empty_simulator(SimulatorConfig::default())
+                    })
                 },
+                "Load session" => load_session().unwrap_or_else(|| empty_simulator(SimulatorConfig::default())),
                 _ => simple_error_exiting(),
             }
         },
@@ -57,6 +66,8 @@ fn menu(simulator: &mut Simulator) -> bool {
         "Interact",
         "Inspect",
         "Save",
+        "Load session",
+        "Settings",
         "New",
         "Exit",
     ];
@@ -73,7 +84,11 @@ fn menu(simulator: &mut Simulator) -> bool {
         "Interact" => interact(simulator),
         "Inspect" => inspect(simulator),
         "Save" => save(simulator),
-        "New" => *simulator = Simulator::new(Circuit::new()),
+        "Load session" => if let Some(loaded) = load_session() {
+            *simulator = loaded;
+        },
+        "Settings" => settings(simulator),
+        "New" => *simulator = empty_simulator(simulator.config().clone()),
         "Exit" => {
             println!("Exiting...");
             return false;
@@ -84,12 +99,112 @@ fn menu(simulator: &mut Simulator) -> bool {
     true
 }
 
+/// Lets the user change the settings and restarts the simulation of the
+/// current circuit with them.
+fn settings(simulator: &mut Simulator) {
+    let current = simulator.config().clone();
+
+    let steps_answer = CustomType::<NonZeroUsize>::new("After how many steps is a simulation unstable?")
+        .with_default(current.steps_until_unstable)
+        .prompt();
+    let Ok(steps_until_unstable) = steps_answer else {
+        simple_error();
+        return;
+    };
+
+    let initial_value_options = vec!["All off", "All on", "Random", "Explicit"];
+    let initial_values = match Select::new("Which values should the simulation start with?", initial_value_options).prompt() {
+        Ok("All on") => InitialValues::AllOn,
+        Ok("Random") => match choose_seed() {
+            Some(seed) => InitialValues::Random { seed },
+            None => return,
+        },
+        Ok("Explicit") => {
+            let values_answer = Text::new("Enter the initial values as bits, starting with value 0:")
+                .with_validator(|text: &str| if text.chars().all(|ch| ch == '0' || ch == '1') {
+                    Ok(Validation::Valid)
+                } else {
+                    Ok(Validation::Invalid("Only 0 and 1 are allowed.".into()))
+                })
+                .prompt();
+
+            match values_answer {
+                Ok(text) => InitialValues::Explicit(text.chars().map(|ch| if ch == '1' { Value::On } else { Value::Off }).collect()),
+                Err(_) => {
+                    simple_error();
+                    return;
+                },
+            }
+        },
+        Ok(_) => InitialValues::AllOff,
+        Err(_) => {
+            simple_error();
+            return;
+        },
+    };
+
+    let event_order_options = vec!["First in, first out", "Last in, first out", "Random"];
+    let event_order = match Select::new("In which order should changed values be propagated?", event_order_options).prompt() {
+        Ok("Last in, first out") => EventOrder::Lifo,
+        Ok("Random") => match choose_seed() {
+            Some(seed) => EventOrder::Random { seed },
+            None => return,
+        },
+        Ok(_) => EventOrder::Fifo,
+        Err(_) => {
+            simple_error();
+            return;
+        },
+    };
+
+    let trace_options = vec!["Value changes", "Component evaluations"];
+    let trace = match MultiSelect::new("What should be traced?", trace_options).prompt() {
+        Ok(choices) => TraceOptions { values: choices.contains(&"Value changes"), components: choices.contains(&"Component evaluations") },
+        Err(_) => {
+            simple_error();
+            return;
+        },
+    };
+
+    let history_limit = match CustomType::<usize>::new("How many operations should be kept to step back?").with_default(simulator.config().history_limit).prompt() {
+        Ok(limit) => limit,
+        Err(_) => {
+            simple_error();
+            return;
+        },
+    };
+
+    let config = SimulatorConfig { steps_until_unstable, initial_values, event_order, trace, history_limit };
+    match Simulator::with_config(simulator.circuit().clone(), config) {
+        Ok(restarted) => {
+            *simulator = restarted;
+            println!("The simulation has been restarted with the new settings.");
+        },
+        Err(error) => println!("The simulation could not be restarted: {error}"),
+    }
+}
+
+fn empty_simulator(config: SimulatorConfig) -> Simulator {
+    Simulator::with_config(Circuit::new(), config).expect("an empty circuit is always valid")
+}
+
+fn choose_seed() -> Option<u64> {
+    match CustomType::<u64>::new("Which seed should be used?").with_default(0).prompt() {
+        Ok(seed) => Some(seed),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
 fn add(simulator: &mut Simulator) {
 
     let element_options = &[
         INPUT,
         OUTPUT,
         COMPONENT,
+        BUS,
     ];
 
     let element_answer = Select::new("Which element should be added?", element_options.to_vec()).prompt();
@@ -100,6 +215,7 @@ fn add(simulator: &mut Simulator) {
             INPUT => add_input(simulator),
             OUTPUT => add_output(simulator),
             COMPONENT => add_component(simulator),
+            BUS => add_bus(simulator),
             _ => (),
         }
 
@@ -143,13 +259,44 @@ fn add_component(simulator: &mut Simulator) {
         Function::Nand,
         Function::Nor,
         Function::FlipFlopRS,
-        Function::FlipFlopJK,
-        Function::FlipFlopD,
-        Function::FlipFlopT,
+        Function::FlipFlopJK { edge: ClockEdge::Rising },
+        Function::FlipFlopD { edge: ClockEdge::Rising },
+        Function::FlipFlopT { edge: ClockEdge::Rising },
+        Function::LatchD,
+        Function::LatchSR,
+        Function::TruthTable(TruthTable::new(1, 1).unwrap()),
+        Function::StateMachine(StateMachine::new(MachineKind::Moore, vec![], vec![])),
+        Function::On,
+        Function::Off,
+        Function::Clock { period: NonZeroUsize::new(2).unwrap(), duty_cycle: 1 },
+        Function::Multiplexer { select_lines: 1 },
+        Function::Demultiplexer { select_lines: 1 },
+        Function::Decoder { width: 1 },
+        Function::Encoder { width: 1 },
+        Function::PriorityEncoder { width: 1 },
+        Function::HalfAdder,
+        Function::FullAdder,
+        Function::Adder { width: 1 },
+        Function::Alu { width: 1 },
+        Function::Counter { width: 1, modulus: 2, edge: ClockEdge::Rising },
+        Function::ShiftRegisterSipo { length: 1, edge: ClockEdge::Rising },
+        Function::ShiftRegisterPiso { length: 1, edge: ClockEdge::Rising },
+        Function::ShiftRegisterUniversal { length: 1, edge: ClockEdge::Rising },
+        Function::FlipFlop { kind: FlipFlopKind::D, options: FlipFlopOptions::default() },
+        Function::Register { width: 1, options: FlipFlopOptions::default() },
+        Function::Ram { address_width: 1, data_width: 1, edge: ClockEdge::Rising },
+        Function::Rom { address_width: 1, data_width: 1, image: RomImage::new("".into(), MemoryFormat::Hex) },
+        Function::Comparator { width: 1, signed: false },
+        Function::TriStateBuffer { width: 1 },
+        Function::OpenDrain { width: 1 },
+        Function::OpenSource { width: 1 },
         Function::Circuit(Circuit::new()),
+        Function::Subcircuit(SubcircuitReference::new(String::new(), 0)),
     ];
 
-    let applicable_functions: Vec<_> = functions.iter().filter(|function| function.input_value_count() <= simulator.values().len()).collect();
+    let applicable_functions: Vec<_> = functions.iter()
+        .filter(|function| matches!(function, Function::Subcircuit(_)) || function.input_value_count() <= simulator.values().len())
+        .collect();
 
     if applicable_functions.is_empty() {
         println!("There are no components that can be created because there are to few values that could be used as inputs.");
@@ -162,9 +309,118 @@ fn add_component(simulator: &mut Simulator) {
         Ok(function_choice) => {
             let function = match function_choice {
                 Function::Circuit(_) => Function::Circuit(load()),
+                Function::Subcircuit(_) => match choose_subcircuit() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::Clock { .. } => match choose_clock() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::FlipFlopJK { .. } => match choose_clock_edge() {
+                    Some(edge) => Function::FlipFlopJK { edge },
+                    None => return,
+                },
+                Function::FlipFlopD { .. } => match choose_clock_edge() {
+                    Some(edge) => Function::FlipFlopD { edge },
+                    None => return,
+                },
+                Function::FlipFlopT { .. } => match choose_clock_edge() {
+                    Some(edge) => Function::FlipFlopT { edge },
+                    None => return,
+                },
+                Function::Multiplexer { .. } => match choose_count("How many select lines should the multiplexer have?") {
+                    Some(select_lines) => Function::Multiplexer { select_lines },
+                    None => return,
+                },
+                Function::Demultiplexer { .. } => match choose_count("How many select lines should the demultiplexer have?") {
+                    Some(select_lines) => Function::Demultiplexer { select_lines },
+                    None => return,
+                },
+                Function::Decoder { .. } => match choose_count("How many inputs should the decoder have?") {
+                    Some(width) => Function::Decoder { width },
+                    None => return,
+                },
+                Function::Encoder { .. } => match choose_count("How many outputs besides the valid output should the encoder have?") {
+                    Some(width) => Function::Encoder { width },
+                    None => return,
+                },
+                Function::PriorityEncoder { .. } => match choose_count("How many outputs besides the valid output should the priority encoder have?") {
+                    Some(width) => Function::PriorityEncoder { width },
+                    None => return,
+                },
+                Function::Adder { .. } => match choose_count("How many bits wide should the adder be?") {
+                    Some(width) => Function::Adder { width },
+                    None => return,
+                },
+                Function::Alu { .. } => match choose_count("How many bits wide should the ALU be?") {
+                    Some(width) => Function::Alu { width },
+                    None => return,
+                },
+                Function::Counter { .. } => match choose_counter() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::ShiftRegisterSipo { .. } => match choose_shift_register() {
+                    Some((length, edge)) => Function::ShiftRegisterSipo { length, edge },
+                    None => return,
+                },
+                Function::ShiftRegisterPiso { .. } => match choose_shift_register() {
+                    Some((length, edge)) => Function::ShiftRegisterPiso { length, edge },
+                    None => return,
+                },
+                Function::ShiftRegisterUniversal { .. } => match choose_shift_register() {
+                    Some((length, edge)) => Function::ShiftRegisterUniversal { length, edge },
+                    None => return,
+                },
+                Function::FlipFlop { .. } => match choose_flip_flop() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::Register { .. } => match choose_register() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::Ram { .. } => match choose_ram() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::Rom { .. } => match choose_rom() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::TruthTable(_) => match choose_truth_table() {
+                    Some(table) => Function::TruthTable(table),
+                    None => return,
+                },
+                Function::StateMachine(_) => match choose_state_machine() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::Comparator { .. } => match choose_comparator() {
+                    Some(function) => function,
+                    None => return,
+                },
+                Function::TriStateBuffer { .. } => match choose_count("How many bits wide should the tri-state buffer be?") {
+                    Some(width) => Function::TriStateBuffer { width },
+                    None => return,
+                },
+                Function::OpenDrain { .. } => match choose_count("How many bits wide should the open-drain output be?") {
+                    Some(width) => Function::OpenDrain { width },
+                    None => return,
+                },
+                Function::OpenSource { .. } => match choose_count("How many bits wide should the open-source output be?") {
+                    Some(width) => Function::OpenSource { width },
+                    None => return,
+                },
                 _ => function_choice.clone(),
             };
 
+            if function.input_value_count() > simulator.values().len() {
+                println!("There are to few values that could be used as inputs for this component.");
+                return;
+            }
+
             let input_value_indices: Vec<_> = (0..simulator.values().len()).collect();
 
             let valid_input_number = function.input_value_count();
@@ -183,13 +439,87 @@ fn add_component(simulator: &mut Simulator) {
                 .prompt();
 
             match input_answer {
-                Ok(input_choice) => {
-                    let (component_index, output_indices) = simulator.add_component(function.clone(), input_choice.clone());
-                    println!("Component with index {component_index} using function {function} on inputs {input_choice:?} with outputs {output_indices:?} has been added.")
+                Ok(input_choice) if function.is_bus_driver() && !simulator.circuit().all_buses().is_empty() => {
+                    add_bus_driver(simulator, function, input_choice);
+                },
+                Ok(input_choice) => match simulator.add_component(function.clone(), input_choice.clone()) {
+                    Ok((component_index, output_indices)) => println!("Component with index {component_index} using function {function} on inputs {input_choice:?} with outputs {output_indices:?} has been added."),
+                    Err(error) => println!("The component could not be added: {error}"),
+                },
+                Err(_) => simple_error(),
+            }
+        },
+        Err(_) => simple_error(),
+    }
+}
+
+fn add_bus(simulator: &mut Simulator) {
+    let Some(width) = choose_count("How many bits wide should the bus be?") else {
+        return;
+    };
+
+    let Some((resolution, pull)) = choose_bus_resolution() else {
+        return;
+    };
+
+    let bus_value_indices = simulator.add_bus(width, resolution, pull);
+    println!("New bus with value indices {bus_value_indices:?} has been added.");
+}
+
+fn choose_bus_resolution() -> Option<(BusResolution, Option<Value>)> {
+    let resolutions = vec![BusResolution::TriState, BusResolution::WiredAnd, BusResolution::WiredOr];
+    let resolution = match Select::new("How should the drivers of the bus be combined?", resolutions).prompt() {
+        Ok(resolution) => resolution,
+        Err(_) => {
+            simple_error();
+            return None;
+        },
+    };
+
+    let pulls = vec!["None", "Pull-up", "Pull-down"];
+    match Select::new("Which value should the bus have without active drivers?", pulls).prompt() {
+        Ok("Pull-up") => Some((resolution, Some(Value::On))),
+        Ok("Pull-down") => Some((resolution, Some(Value::Off))),
+        Ok(_) => Some((resolution, None)),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
+fn add_bus_driver(simulator: &mut Simulator, function: Function, input_choice: Vec<usize>) {
+    let drive_answer = Confirm::new("Should the component drive existing buses?").with_default(true).prompt();
+
+    match drive_answer {
+        Ok(true) => {
+            let bus_value_indices: Vec<_> = simulator.circuit().all_buses().iter().map(|bus| bus.value_index()).collect();
+
+            let valid_output_number = function.output_value_count();
+            let validator = move |a: &[ListOption<&usize>]| {
+                if a.len() != valid_output_number {
+                    Ok(Validation::Invalid(format!("Exactly {valid_output_number} buses have to be selected.").into()))
+                } else {
+                    Ok(Validation::Valid)
+                }
+            };
+
+            let bus_answer = MultiSelect::new("Choose the buses the component should drive:", bus_value_indices)
+                .with_validator(validator)
+                .prompt();
+
+            match bus_answer {
+                Ok(bus_choice) => match simulator.add_bus_driver(function.clone(), input_choice.clone(), bus_choice.clone()) {
+                    Ok(component_index) => println!("Component with index {component_index} using function {function} on inputs {input_choice:?} driving buses {bus_choice:?} has been added."),
+                    Err(error) => println!("The component could not be added: {error}"),
                 },
                 Err(_) => simple_error(),
             }
         },
+        Ok(false) => match simulator.add_component(function.clone(), input_choice.clone()) {
+            Ok((component_index, output_indices)) => println!("Component with index {component_index} using function {function} on inputs {input_choice:?} with outputs {output_indices:?} has been added."),
+            Err(error) => println!("The component could not be added: {error}"),
+        },
         Err(_) => simple_error(),
     }
 }
@@ -200,6 +530,17 @@ fn interact(simulator: &mut Simulator) {
         "Set input",
         "Simulate",
         "Step",
+        "Tick",
+        "Simulate ticks",
+        "Step back",
+        "Go to step",
+        "Compare steps",
+        "Breakpoints",
+        "Load memory",
+        "Save memory",
+        "Edit truth table",
+        "Set initial state",
+        "Reset",
     ];
 
     let interaction_answer = Select::new("Which interaction do you want to do?", interaction_options.to_vec()).prompt();
@@ -209,6 +550,20 @@ fn interact(simulator: &mut Simulator) {
             "Set input" => set_input(simulator),
             "Simulate" => simulate(simulator),
             "Step" => simulate_step(simulator),
+            "Tick" => simulate_tick(simulator),
+            "Simulate ticks" => simulate_ticks(simulator),
+            "Step back" => step_back(simulator),
+            "Go to step" => go_to_step(simulator),
+            "Compare steps" => compare_steps(simulator),
+            "Breakpoints" => breakpoints(simulator),
+            "Load memory" => load_memory(simulator),
+            "Save memory" => save_memory(simulator),
+            "Edit truth table" => edit_truth_table(simulator),
+            "Set initial state" => set_initial_state(simulator),
+            "Reset" => {
+                simulator.reset();
+                println!("The simulation has been reset to its initial state.");
+            },
             _ => simple_error()
         }
     }
@@ -238,10 +593,17 @@ fn set_input(simulator: &mut Simulator) {
 }
 
 fn simulate(simulator: &mut Simulator) {
-    if simulator.simulate() {
-        println!("Simulation ran into stable condition.");
-    } else {
-        println!("Simulation finished in unstable condition.");
+    match simulator.simulate() {
+        Ok(()) => println!("Simulation ran into stable condition."),
+        Err(Interruption::Oscillation(oscillation)) => {
+            println!("Simulation finished in unstable condition.");
+            print_oscillation(&oscillation);
+        },
+        Err(Interruption::Breakpoint(hit)) => println!("Simulation stopped at {hit}."),
+    }
+
+    for value_index in simulator.bus_contentions() {
+        println!("\tBus contention on value {value_index}.");
     }
 
     simulator.circuit().all_outputs().iter()
@@ -250,6 +612,16 @@ fn simulate(simulator: &mut Simulator) {
         .for_each(|(output_index, value)| println!("\tOutput {output_index} has value {value}."));
 }
 
+fn print_oscillation(oscillation: &Oscillation) {
+    match oscillation.period {
+        Some(period) => println!("\tThe state repeats every {period} steps."),
+        None => println!("\tNo repeating state has been found."),
+    }
+
+    println!("\tToggling values: {:?}", oscillation.toggling_values);
+    println!("\tComponents in the feedback loop: {:?}", oscillation.feedback_components);
+}
+
 fn simulate_step(simulator: &mut Simulator) {
     simulator.step();
     println!("Stepped");
@@ -260,123 +632,821 @@ fn simulate_step(simulator: &mut Simulator) {
         .for_each(|(output_index, value)| println!("\tOutput {output_index} has value {value}."));
 }
 
-fn inspect(simulator: &mut Simulator) {
+fn step_back(simulator: &mut Simulator) {
+    if simulator.step_back() {
+        println!("Stepped back to step {}", simulator.step_count());
+    } else {
+        println!("There is nothing left to step back.");
+    }
 
-    let inspect_options = &[
-        INPUT,
-        OUTPUT,
-        COMPONENT,
-        VALUE,
-    ];
+    simulator.circuit().all_outputs().iter()
+        .map(|output| simulator.value_for_output(output))
+        .enumerate()
+        .for_each(|(output_index, value)| println!("\tOutput {output_index} has value {value}."));
+}
 
-    let inspect_answer = Select::new("Which element should be inspected?", inspect_options.to_vec()).prompt();
+fn go_to_step(simulator: &mut Simulator) {
+    let prompt = format!("Which step should be gone to? (current {}, oldest {})", simulator.step_count(), simulator.oldest_step());
+    let step_answer = CustomType::<usize>::new(&prompt).prompt();
 
-    if let Ok(inspect_choice) = inspect_answer {
+    if let Ok(step_choice) = step_answer {
+        if simulator.go_to_step(step_choice) {
+            println!("Went to step {step_choice}");
+        } else {
+            println!("Step {step_choice} could not be reached, stopped at step {}.", simulator.step_count());
+        }
 
-        let select_options = &[
-            ALL,
-            BY_INDEX,
-        ];
+        simulator.circuit().all_outputs().iter()
+            .map(|output| simulator.value_for_output(output))
+            .enumerate()
+            .for_each(|(output_index, value)| println!("\tOutput {output_index} has value {value}."));
+    } else {
+        simple_error();
+    }
+}
 
-        let select_answer = Select::new("Do you want to inspect all or by index?", select_options.to_vec()).prompt();
+fn compare_steps(simulator: &mut Simulator) {
+    let first_answer = CustomType::<usize>::new("Which step should be compared?").with_default(simulator.oldest_step()).prompt();
+    let second_answer = CustomType::<usize>::new("With which step?").with_default(simulator.step_count()).prompt();
 
-        if let Ok(select_choice) = select_answer {
+    let (Ok(first_step), Ok(second_step)) = (first_answer, second_answer) else {
+        simple_error();
+        return;
+    };
 
-            match select_choice {
-                ALL => {
-                    match inspect_choice {
-                        INPUT => {
-                            println!("Inspecting all inputs:");
+    match simulator.compare_steps(first_step, second_step) {
+        Some(differences) if differences.is_empty() => println!("The values at step {first_step} and {second_step} are the same."),
+        Some(differences) => {
+            for (value_index, first_value, second_value) in differences {
+                println!("\tValue {value_index} changed from {first_value} to {second_value}.");
+            }
+        },
+        None => println!("Only steps {} to {} are in the history.", simulator.oldest_step(), simulator.step_count()),
+    }
+}
 
-                            simulator.circuit().all_inputs().iter().enumerate().for_each(|(i, input)| {
-                                println!("Index: {i}\n{input:?}");
-                            });
-                        },
-                        OUTPUT => {
-                            println!("Inspecting all outputs:");
+fn breakpoints(simulator: &mut Simulator) {
+    for (breakpoint_index, breakpoint) in simulator.breakpoints().iter().enumerate() {
+        println!("\tBreakpoint {breakpoint_index}: {breakpoint}");
+    }
 
-                            simulator.circuit().all_outputs().iter().enumerate().for_each(|(i, input)| {
-                                println!("Index: {i}\n{input:?}");
-                            });
-                        },
-                        COMPONENT => {
-                            println!("Inspecting all components:");
+    let breakpoint_options = vec!["Value becomes", "Component is evaluated", "Output changes", "Remove", "Clear"];
 
-                            simulator.circuit().all_components().iter().enumerate().for_each(|(i, input)| {
-                                println!("Index: {i}\n{input:?}");
-                            });
-                        },
-                        VALUE => {
-                            println!("Inspecting all values:");
+    let breakpoint = match Select::new("What do you want to do with the breakpoints?", breakpoint_options).prompt() {
+        Ok("Value becomes") => {
+            let value_index_answer = CustomType::<usize>::new("Which value should be watched?").prompt();
+            let value_answer = Select::new("Which value should it become?", vec![Value::On, Value::Off]).prompt();
 
-                            simulator.values().iter().enumerate().for_each(|(i, input)| {
-                                println!("Index: {i}\n{input:?}");
-                            });
-                        },
-                        _ => {
-                            simple_error();
-                            return;
-                        },
-                    }
+            match (value_index_answer, value_answer) {
+                (Ok(value_index), Ok(value)) if value_index < simulator.values().len() => Breakpoint::ValueBecomes { value_index, value },
+                _ => {
+                    simple_error();
+                    return;
                 },
-                BY_INDEX => {
-                    let choosable_indices: Vec<_> = match inspect_choice {
-                        INPUT => (0..simulator.circuit().all_inputs().len()).collect(),
-                        OUTPUT => (0..simulator.circuit().all_outputs().len()).collect(),
-                        COMPONENT => (0..simulator.circuit().all_components().len()).collect(),
-                        VALUE => (0..simulator.values().len()).collect(),
-                        _ => {
-                            simple_error();
-                            return;
-                        },
-                    };
-
-                    let index_answer = Select::new("Which index should be shown?", choosable_indices).prompt();
+            }
+        },
+        Ok("Component is evaluated") => {
+            let component_index_options = (0..simulator.circuit().all_components().len()).collect();
+
+            match Select::new("Which component should be watched?", component_index_options).prompt() {
+                Ok(component_index) => Breakpoint::ComponentEvaluated { component_index },
+                Err(_) => {
+                    simple_error();
+                    return;
+                },
+            }
+        },
+        Ok("Output changes") => {
+            let output_index_options = (0..simulator.circuit().all_outputs().len()).collect();
+
+            match Select::new("Which output should be watched?", output_index_options).prompt() {
+                Ok(output_index) => Breakpoint::OutputChanges { output_index },
+                Err(_) => {
+                    simple_error();
+                    return;
+                },
+            }
+        },
+        Ok("Remove") => {
+            let breakpoint_index_options = (0..simulator.breakpoints().len()).collect();
 
-                    if let Ok(index_choice) = index_answer {
-                        match inspect_choice {
-                            INPUT => {
-                                let choosen_input = &simulator.circuit().all_inputs()[index_choice];
-                                println!("Inspecting input at index {index_choice}:\n{choosen_input:?}");
-                            },
-                            OUTPUT => {
-                                let choosen_input = &simulator.circuit().all_outputs()[index_choice];
-                                println!("Inspecting output at index {index_choice}:\n{choosen_input:?}");
-                            },
-                            COMPONENT => {
-                                let choosen_input = &simulator.circuit().all_components()[index_choice];
-                                println!("Inspecting component at index {index_choice}:\n{choosen_input:?}");
-                            },
-                            VALUE => {
-                                let choosen_input = &simulator.values()[index_choice];
-                                println!("Inspecting value at index {index_choice}:\n{choosen_input:?}");
-                            },
-                            _ => {
-                                simple_error();
-                                return;
-                            },
-                        }
-                    } else {
-                        simple_error();
+            match Select::new("Which breakpoint should be removed?", breakpoint_index_options).prompt() {
+                Ok(breakpoint_index) => {
+                    if let Some(breakpoint) = simulator.remove_breakpoint(breakpoint_index) {
+                        println!("Breakpoint {breakpoint_index} ({breakpoint}) has been removed.");
                     }
                 },
-                _ => simple_error()
+                Err(_) => simple_error(),
             }
-
-        } else {
+            return;
+        },
+        Ok(_) => {
+            simulator.clear_breakpoints();
+            println!("All breakpoints have been removed.");
+            return;
+        },
+        Err(_) => {
             simple_error();
             return;
-        }
+        },
+    };
 
-    } else {
-        simple_error()
-    }
+    let breakpoint_index = simulator.add_breakpoint(breakpoint);
+    println!("Breakpoint {breakpoint_index} ({breakpoint}) has been added.");
 }
 
-fn save(simulator: &mut Simulator) {
-    if let Ok(serialized_circuit) = serde_json::to_string_pretty(simulator.circuit()) {
+fn simulate_tick(simulator: &mut Simulator) {
+    simulator.tick();
+    println!("Ticked to time {}", simulator.time());
 
-        let current_dir = std::env::current_dir().unwrap();
+    simulator.circuit().all_outputs().iter()
+        .map(|output| simulator.value_for_output(output))
+        .enumerate()
+        .for_each(|(output_index, value)| println!("\tOutput {output_index} has value {value}."));
+}
+
+fn simulate_ticks(simulator: &mut Simulator) {
+    let ticks_answer = CustomType::<usize>::new("How many ticks should be simulated?").prompt();
+
+    if let Ok(ticks_choice) = ticks_answer {
+        match simulator.simulate_ticks(ticks_choice) {
+            Ok(()) => println!("Simulation ran into stable condition at time {}.", simulator.time()),
+            Err(Interruption::Oscillation(oscillation)) => {
+                println!("Simulation finished in unstable condition at time {}.", simulator.time());
+                print_oscillation(&oscillation);
+            },
+            Err(Interruption::Breakpoint(hit)) => println!("Simulation stopped at {hit} at time {}.", simulator.time()),
+        }
+
+        simulator.circuit().all_outputs().iter()
+            .map(|output| simulator.value_for_output(output))
+            .enumerate()
+            .for_each(|(output_index, value)| println!("\tOutput {output_index} has value {value}."));
+    } else {
+        simple_error();
+    }
+}
+
+fn load_memory(simulator: &mut Simulator) {
+    if let Some((component_index, format, (word_count, data_width))) = choose_memory(simulator) {
+        let path = choose_path("Memory file to load:");
+
+        match path.map(|path| memory::load_file(path, format, word_count, data_width)) {
+            Some(Ok(words)) => match simulator.set_memory(component_index, &words) {
+                Ok(()) => println!("{} words have been loaded into component {component_index}.", words.len()),
+                Err(error) => println!("Error while loading memory: {error}"),
+            },
+            Some(Err(error)) => println!("Error while loading memory: {error}"),
+            None => simple_error(),
+        }
+    }
+}
+
+fn save_memory(simulator: &mut Simulator) {
+    if let Some((component_index, format, (_, data_width))) = choose_memory(simulator) {
+        let words = simulator.memory(component_index).unwrap();
+        let path = choose_path("Save location:");
+
+        match path.map(|path| memory::save_file(path, format, data_width, &words)) {
+            Some(Ok(())) => println!("Memory of component {component_index} has been saved."),
+            Some(Err(error)) => println!("Error while saving memory: {error}"),
+            None => simple_error(),
+        }
+    }
+}
+
+/// Lets the user choose a component with memory and a file format and
+/// returns them together with the word count and width of the memory.
+fn choose_memory(simulator: &Simulator) -> Option<(usize, MemoryFormat, (usize, usize))> {
+    let memory_components: Vec<_> = simulator.circuit().all_components().iter()
+        .enumerate()
+        .filter(|(_, component)| component.function().memory_layout().is_some())
+        .map(|(i, _)| i)
+        .collect();
+
+    if memory_components.is_empty() {
+        println!("There are no components with memory.");
+        return None;
+    }
+
+    let Ok(component_index) = Select::new("Which component should be used?", memory_components).prompt() else {
+        simple_error();
+        return None;
+    };
+
+    let Ok(format) = choose_memory_format() else {
+        simple_error();
+        return None;
+    };
+
+    let layout = simulator.circuit().component(component_index).function().memory_layout().unwrap();
+    Some((component_index, format, layout))
+}
+
+fn choose_memory_format() -> Result<MemoryFormat, inquire::InquireError> {
+    let format_options = vec![
+        MemoryFormat::Hex,
+        MemoryFormat::Binary,
+        MemoryFormat::IntelHex,
+        MemoryFormat::LogisimRaw,
+    ];
+
+    Select::new("Which file format should be used?", format_options).prompt()
+}
+
+fn choose_rom() -> Option<Function> {
+    let address_width = choose_count("How many address bits should the ROM have?")?;
+    let data_width = choose_count("How many bits wide should a word be?")?;
+    let path = choose_path("ROM image to load:")?;
+
+    let format = match choose_memory_format() {
+        Ok(format) => format,
+        Err(_) => {
+            simple_error();
+            return None;
+        },
+    };
+
+    let mut image = RomImage::new(path.into(), format);
+
+    match image.load(Path::new(""), address_width, data_width) {
+        Ok(()) => Some(Function::Rom { address_width, data_width, image }),
+        Err(error) => {
+            println!("Error while loading ROM image: {error}");
+            None
+        },
+    }
+}
+
+fn choose_truth_table() -> Option<TruthTable> {
+    let format_options = vec!["Bit string", "Rows", "PLA file", "BLIF file"];
+    let rows = match Select::new("How do you want to enter the table?", format_options).prompt() {
+        Ok("PLA file") => return load_pla(),
+        Ok("BLIF file") => return load_blif(),
+        Ok(format) => format == "Rows",
+        Err(_) => {
+            simple_error();
+            return None;
+        },
+    };
+
+    let input_count = choose_count("How many inputs should the truth table have?")?;
+    let output_count = choose_count("How many outputs should the truth table have?")?;
+
+    let parse = move |text: &str| if rows {
+        TruthTable::from_rows(input_count, output_count, &text.split(',').collect::<Vec<_>>())
+    } else {
+        TruthTable::from_bits(input_count, output_count, text)
+    };
+
+    let message = if rows {
+        "Enter comma separated rows of inputs and outputs like \"1-0 01\", with input 0 first:".to_owned()
+    } else {
+        format!("Enter the {output_count} outputs of each of the {} rows, with input 0 as the least significant bit:", 1 << input_count)
+    };
+
+    let table_answer = Text::new(&message)
+        .with_validator(move |text: &str| match parse(text) {
+            Ok(_) => Ok(Validation::Valid),
+            Err(error) => Ok(Validation::Invalid(error.to_string().into())),
+        })
+        .prompt();
+
+    match table_answer {
+        Ok(text) => parse(&text).ok(),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
+fn load_pla() -> Option<TruthTable> {
+    let path = choose_path("PLA file to load:")?;
+
+    match fs::read_to_string(path).map(|text| TruthTable::from_pla(&text)) {
+        Ok(Ok(table)) => Some(table),
+        Ok(Err(error)) => {
+            println!("Error while parsing PLA file: {error}");
+            None
+        },
+        Err(error) => {
+            println!("Error while loading PLA file: {error}");
+            None
+        },
+    }
+}
+
+fn load_blif() -> Option<TruthTable> {
+    let path = choose_path("BLIF file to load:")?;
+
+    match fs::read_to_string(path).map(|text| TruthTable::from_blif(&text)) {
+        Ok(Ok(table)) => Some(table),
+        Ok(Err(error)) => {
+            println!("Error while parsing BLIF file: {error}");
+            None
+        },
+        Err(error) => {
+            println!("Error while loading BLIF file: {error}");
+            None
+        },
+    }
+}
+
+/// Enters a state table or loads it from a JSON file and either uses it
+/// directly or synthesizes it into a circuit with the chosen state encoding.
+fn choose_state_machine() -> Option<Function> {
+    let source_options = vec!["Enter", "Load"];
+    let machine = match Select::new("Do you want to enter the state table or load it from a file?", source_options).prompt() {
+        Ok("Enter") => enter_state_machine()?,
+        Ok(_) => load_state_machine()?,
+        Err(_) => {
+            simple_error();
+            return None;
+        },
+    };
+
+    let implementation_options = vec!["State machine", "Binary", "OneHot", "Gray"];
+    match Select::new("Should the state machine be synthesized with one of these encodings?", implementation_options).prompt() {
+        Ok("Binary") => Some(Function::Circuit(machine.synthesize(StateEncoding::Binary))),
+        Ok("OneHot") => Some(Function::Circuit(machine.synthesize(StateEncoding::OneHot))),
+        Ok("Gray") => Some(Function::Circuit(machine.synthesize(StateEncoding::Gray))),
+        Ok(_) => Some(Function::StateMachine(machine)),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
+fn load_state_machine() -> Option<StateMachine> {
+    let path = choose_path("State table to load:")?;
+
+    match fs::read_to_string(path).map(|json| serde_json::from_str::<StateMachine>(&json)) {
+        Ok(Ok(machine)) => Some(machine),
+        Ok(Err(error)) => {
+            println!("Error while parsing state table: {error}");
+            None
+        },
+        Err(error) => {
+            println!("Error while loading state table: {error}");
+            None
+        },
+    }
+}
+
+/// Asks for the states and then the transitions of a state machine.
+fn enter_state_machine() -> Option<StateMachine> {
+    let Ok(kind) = Select::new("Which kind of state machine should it be?", vec![MachineKind::Moore, MachineKind::Mealy]).prompt() else {
+        simple_error();
+        return None;
+    };
+
+    let Ok(inputs) = Text::new("Names of the inputs, separated by spaces:").prompt() else {
+        simple_error();
+        return None;
+    };
+
+    let Ok(outputs) = Text::new("Names of the outputs, separated by spaces:").prompt() else {
+        simple_error();
+        return None;
+    };
+
+    let inputs = inputs.split_whitespace().map(str::to_owned).collect();
+    let outputs = outputs.split_whitespace().map(str::to_owned).collect();
+    let mut machine = StateMachine::new(kind, inputs, outputs);
+    machine.set_edge(choose_clock_edge()?);
+    let bits_help = "Bits with output 0 first, leave empty for all Off";
+
+    loop {
+        let help_message = if machine.state_count() == 0 { "The first state is the initial state" } else { "Leave empty to continue with the transitions" };
+        let Ok(name) = Text::new("Name of the next state:").with_help_message(help_message).prompt() else {
+            simple_error();
+            return None;
+        };
+
+        if name.is_empty() {
+            if machine.state_count() > 0 {
+                break;
+            }
+
+            println!("A state machine needs at least one state.");
+            continue;
+        }
+
+        let state_outputs = if kind == MachineKind::Moore {
+            let Ok(state_outputs) = Text::new(&format!("Outputs in state {name}:")).with_help_message(bits_help).prompt() else {
+                simple_error();
+                return None;
+            };
+
+            state_outputs
+        } else {
+            String::new()
+        };
+
+        if let Err(error) = machine.add_state(&name, &state_outputs) {
+            println!("The state could not be added: {error}");
+        }
+    }
+
+    let state_names: Vec<String> = (0..machine.state_count()).map(|state| machine.state_name(state).to_owned()).collect();
+
+    loop {
+        let Ok(add_transition) = Confirm::new("Do you want to add a transition?").with_default(true).prompt() else {
+            simple_error();
+            return None;
+        };
+
+        if !add_transition {
+            break;
+        }
+
+        let Ok(from) = Select::new("From which state?", state_names.clone()).prompt() else {
+            simple_error();
+            return None;
+        };
+
+        let Ok(condition) = Text::new("On which inputs?").with_help_message("Bits with input 0 first and - for either value, leave empty to always match").prompt() else {
+            simple_error();
+            return None;
+        };
+
+        let Ok(to) = Select::new("To which state?", state_names.clone()).prompt() else {
+            simple_error();
+            return None;
+        };
+
+        let transition_outputs = if kind == MachineKind::Mealy {
+            let Ok(transition_outputs) = Text::new("Outputs while the transition matches:").with_help_message(bits_help).prompt() else {
+                simple_error();
+                return None;
+            };
+
+            transition_outputs
+        } else {
+            String::new()
+        };
+
+        if let Err(error) = machine.add_transition(&from, &condition, &to, &transition_outputs) {
+            println!("The transition could not be added: {error}");
+        }
+    }
+
+    Some(machine)
+}
+
+fn set_initial_state(simulator: &mut Simulator) {
+    let stateful_components: Vec<_> = simulator.circuit().all_components().iter()
+        .enumerate()
+        .filter(|(_, component)| !component.owned_value_indices().is_empty())
+        .map(|(i, _)| i)
+        .collect();
+
+    if stateful_components.is_empty() {
+        println!("There are no components with a state.");
+        return;
+    }
+
+    let Ok(component_index) = Select::new("Which component should get an initial state?", stateful_components).prompt() else {
+        simple_error();
+        return;
+    };
+
+    let owned_value_count = simulator.circuit().component(component_index).owned_value_indices().len();
+    let state_answer = Text::new("Which values should it start with? (bits of the stored values, rest Off)")
+        .with_validator(move |text: &str| if text.len() > owned_value_count {
+            Ok(Validation::Invalid(format!("The component only stores {owned_value_count} values.").into()))
+        } else if text.chars().all(|ch| ch == '0' || ch == '1') {
+            Ok(Validation::Valid)
+        } else {
+            Ok(Validation::Invalid("Only 0 and 1 are allowed.".into()))
+        })
+        .prompt();
+
+    match state_answer {
+        Ok(text) => {
+            let initial_state = text.chars().map(|ch| if ch == '1' { Value::On } else { Value::Off }).collect();
+            match simulator.set_initial_state(component_index, initial_state) {
+                Ok(()) => println!("The initial state of component {component_index} has been set to {text}, it is used from the next reset."),
+                Err(error) => println!("The initial state could not be set: {error}"),
+            }
+        },
+        Err(_) => simple_error(),
+    }
+}
+
+fn edit_truth_table(simulator: &mut Simulator) {
+    let table_components: Vec<_> = simulator.circuit().all_components().iter()
+        .enumerate()
+        .filter(|(_, component)| matches!(component.function(), Function::TruthTable(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    if table_components.is_empty() {
+        println!("There are no truth table components.");
+        return;
+    }
+
+    let Ok(component_index) = Select::new("Which truth table should be edited?", table_components).prompt() else {
+        simple_error();
+        return;
+    };
+
+    let Function::TruthTable(table) = simulator.circuit().component(component_index).function() else {
+        return;
+    };
+    let mut table = table.clone();
+
+    for row in 0..table.row_count() {
+        let inputs: String = (0..table.input_count()).map(|i| if row >> i & 1 == 1 { '1' } else { '0' }).collect();
+        let outputs: String = table.row(row).iter().map(|&value| if value == Value::On { '1' } else { '0' }).collect();
+        println!("\tRow {row}: {inputs} {outputs}");
+    }
+
+    let row_count = table.row_count();
+    let row_answer = CustomType::<usize>::new("Which row should be changed?")
+        .with_validator(move |&row: &usize| {
+            if row < row_count {
+                Ok(Validation::Valid)
+            } else {
+                Ok(Validation::Invalid(format!("The table only has {row_count} rows.").into()))
+            }
+        })
+        .prompt();
+
+    let output_count = table.output_count();
+    let parse_outputs = move |text: &str| -> Option<Vec<Value>> {
+        let values: Option<Vec<_>> = text.trim().chars().map(|ch| match ch {
+            '0' => Some(Value::Off),
+            '1' => Some(Value::On),
+            _ => None,
+        }).collect();
+        values.filter(|values| values.len() == output_count)
+    };
+    let outputs_answer = Text::new("New outputs of the row, with output 0 first:")
+        .with_validator(move |text: &str| match parse_outputs(text) {
+            Some(_) => Ok(Validation::Valid),
+            None => Ok(Validation::Invalid(format!("Enter exactly {output_count} bits.").into())),
+        })
+        .prompt();
+
+    match (row_answer, outputs_answer.map(|text| parse_outputs(&text))) {
+        (Ok(row), Ok(Some(outputs))) => {
+            table.set_row(row, &outputs);
+            simulator.set_truth_table(component_index, table);
+            println!("Row {row} of component {component_index} has been changed.");
+        },
+        _ => simple_error(),
+    }
+}
+
+fn inspect_memory(simulator: &Simulator) {
+    let memory_components: Vec<_> = (0..simulator.circuit().all_components().len())
+        .filter(|&component_index| simulator.memory(component_index).is_some())
+        .collect();
+
+    if memory_components.is_empty() {
+        println!("There are no components with memory.");
+        return;
+    }
+
+    let Ok(component_index) = Select::new("Which component should be inspected?", memory_components).prompt() else {
+        simple_error();
+        return;
+    };
+
+    let Ok(address) = CustomType::<usize>::new("Around which address should the memory be shown?").prompt() else {
+        simple_error();
+        return;
+    };
+
+    let words = simulator.memory(component_index).unwrap();
+    let digits = format!("{:x}", words.len().saturating_sub(1)).len();
+    let start = address.saturating_sub(8) & !7;
+    let end = (start + 24).min(words.len());
+
+    println!("Inspecting memory of component {component_index}:");
+
+    for line_start in (start..end).step_by(8) {
+        let line = (line_start..(line_start + 8).min(end))
+            .map(|i| if i == address { format!("[{:x}]", words[i]) } else { format!(" {:x} ", words[i]) })
+            .collect::<String>();
+        println!("{line_start:0digits$x}:{line}");
+    }
+}
+
+fn choose_path(message: &str) -> Option<String> {
+    let current_dir = std::env::current_dir().unwrap();
+    let help_message = format!("Current directory: {}", current_dir.to_string_lossy());
+
+    Text::new(message)
+        .with_autocomplete(cli_util::FilePathCompleter::default())
+        .with_help_message(&help_message)
+        .prompt()
+        .ok()
+}
+
+fn inspect_trace(simulator: &mut Simulator) {
+    if simulator.trace().is_empty() {
+        println!("The trace is empty. Tracing can be enabled in the settings.");
+        return;
+    }
+
+    for event in simulator.trace() {
+        match event {
+            TraceEvent::ValueChanged { step, value_index, value } => println!("\tStep {step}: value {value_index} changed to {value}."),
+            TraceEvent::ComponentEvaluated { step, component_index } => println!("\tStep {step}: component {component_index} was evaluated."),
+        }
+    }
+
+    if let Ok(true) = Confirm::new("Should the trace be cleared?").with_default(false).prompt() {
+        simulator.clear_trace();
+    }
+}
+
+fn inspect_cause(simulator: &Simulator) {
+    let value_count = simulator.values().len();
+    let value_answer = CustomType::<usize>::new("Which value should be explained?")
+        .with_validator(move |&value_index: &usize| {
+            if value_index < value_count {
+                Ok(Validation::Valid)
+            } else {
+                Ok(Validation::Invalid(format!("There are only {value_count} values.").into()))
+            }
+        })
+        .prompt();
+
+    match value_answer {
+        Ok(value_index) => print_explanation(simulator, &simulator.explain(value_index), ""),
+        Err(_) => simple_error(),
+    }
+}
+
+/// Prints the explanation as a tree with the trigger of every cause below it.
+fn print_explanation(simulator: &Simulator, explanation: &Explanation, indent: &str) {
+    println!("{indent}Value {} is {}", explanation.value_index, explanation.value);
+
+    let Some(cause) = explanation.cause else {
+        println!("{indent}└─ unchanged since the simulation started");
+        return;
+    };
+
+    match cause.source {
+        CauseSource::Input { input_index } => println!("{indent}└─ set by input {input_index} at step {}", cause.step),
+        CauseSource::Edit => println!("{indent}└─ resolved again after its bus was edited at step {}", cause.step),
+        CauseSource::Component { component_index, trigger } => {
+            let function = simulator.circuit().component(component_index).function();
+            println!("{indent}└─ set by component {component_index} ({function}) at step {}", cause.step);
+
+            let child_indent = format!("{indent}   ");
+            match (trigger, &explanation.trigger) {
+                (_, Some(trigger_explanation)) => print_explanation(simulator, trigger_explanation, &child_indent),
+                (Some(trigger), None) => println!("{child_indent}Value {trigger} triggered it, but has changed again since"),
+                (None, None) => println!("{child_indent}evaluated by a tick or an edit"),
+            }
+        },
+    }
+}
+
+fn inspect(simulator: &mut Simulator) {
+
+    let inspect_options = &[
+        INPUT,
+        OUTPUT,
+        COMPONENT,
+        VALUE,
+        MEMORY,
+        TRACE,
+        CAUSE,
+    ];
+
+    let inspect_answer = Select::new("Which element should be inspected?", inspect_options.to_vec()).prompt();
+
+    if let Ok(inspect_choice) = inspect_answer {
+
+        if inspect_choice == MEMORY {
+            inspect_memory(simulator);
+            return;
+        }
+
+        if inspect_choice == TRACE {
+            inspect_trace(simulator);
+            return;
+        }
+
+        if inspect_choice == CAUSE {
+            inspect_cause(simulator);
+            return;
+        }
+
+        let select_options = &[
+            ALL,
+            BY_INDEX,
+        ];
+
+        let select_answer = Select::new("Do you want to inspect all or by index?", select_options.to_vec()).prompt();
+
+        if let Ok(select_choice) = select_answer {
+
+            match select_choice {
+                ALL => {
+                    match inspect_choice {
+                        INPUT => {
+                            println!("Inspecting all inputs:");
+
+                            simulator.circuit().all_inputs().iter().enumerate().for_each(|(i, input)| {
+                                println!("Index: {i}\n{input:?}");
+                            });
+                        },
+                        OUTPUT => {
+                            println!("Inspecting all outputs:");
+
+                            simulator.circuit().all_outputs().iter().enumerate().for_each(|(i, input)| {
+                                println!("Index: {i}\n{input:?}");
+                            });
+                        },
+                        COMPONENT => {
+                            println!("Inspecting all components:");
+
+                            simulator.circuit().all_components().iter().enumerate().for_each(|(i, input)| {
+                                println!("Index: {i}\n{input:?}");
+                            });
+                        },
+                        VALUE => {
+                            println!("Inspecting all values:");
+
+                            simulator.values().iter().enumerate().for_each(|(i, input)| {
+                                println!("Index: {i}\n{input:?}");
+                            });
+                        },
+                        _ => simple_error(),
+                    }
+                },
+                BY_INDEX => {
+                    let choosable_indices: Vec<_> = match inspect_choice {
+                        INPUT => (0..simulator.circuit().all_inputs().len()).collect(),
+                        OUTPUT => (0..simulator.circuit().all_outputs().len()).collect(),
+                        COMPONENT => (0..simulator.circuit().all_components().len()).collect(),
+                        VALUE => (0..simulator.values().len()).collect(),
+                        _ => {
+                            simple_error();
+                            return;
+                        },
+                    };
+
+                    let index_answer = Select::new("Which index should be shown?", choosable_indices).prompt();
+
+                    if let Ok(index_choice) = index_answer {
+                        match inspect_choice {
+                            INPUT => {
+                                let choosen_input = &simulator.circuit().all_inputs()[index_choice];
+                                println!("Inspecting input at index {index_choice}:\n{choosen_input:?}");
+                            },
+                            OUTPUT => {
+                                let choosen_input = &simulator.circuit().all_outputs()[index_choice];
+                                println!("Inspecting output at index {index_choice}:\n{choosen_input:?}");
+                            },
+                            COMPONENT => {
+                                let choosen_input = &simulator.circuit().all_components()[index_choice];
+                                println!("Inspecting component at index {index_choice}:\n{choosen_input:?}");
+                            },
+                            VALUE => {
+                                let choosen_input = &simulator.values()[index_choice];
+                                println!("Inspecting value at index {index_choice}:\n{choosen_input:?}");
+                            },
+                            _ => simple_error(),
+                        }
+                    } else {
+                        simple_error();
+                    }
+                },
+                _ => simple_error()
+            }
+
+        } else {
+            simple_error();
+        }
+
+    } else {
+        simple_error()
+    }
+}
+
+fn save(simulator: &mut Simulator) {
+    let save_options = vec!["Circuit", "Session"];
+    let serialized = match Select::new("Should only the circuit or the whole session including its state be saved?", save_options).prompt() {
+        Ok("Session") => serde_json::to_string_pretty(&simulator.session()),
+        Ok(_) => serde_json::to_string_pretty(simulator.circuit()),
+        Err(_) => {
+            simple_error();
+            return;
+        },
+    };
+
+    if let Ok(serialized_circuit) = serialized {
+
+        let current_dir = std::env::current_dir().unwrap();
         let help_message = format!("Current directory: {}", current_dir.to_string_lossy());
 
         let save_location_answer = Text::new("Save location:")
@@ -387,7 +1457,7 @@ fn save(simulator: &mut Simulator) {
         if let Ok(save_location_choice) = save_location_answer {
             if let Ok(mut file) = fs::File::create(save_location_choice) {
                 if file.write(serialized_circuit.as_bytes()).is_ok() {
-                    println!("Circuit has been saved.");
+                    println!("Saving has been successful.");
                 }
             }
 
@@ -410,8 +1480,12 @@ fn load() -> Circuit {
         .prompt();
 
     if let Ok(file_to_load_choice) = file_to_load_answer {
-        let serial_circuit = fs::read(file_to_load_choice).unwrap();
-        let loaded_circuit: Circuit = serde_json::from_slice(&serial_circuit).unwrap();
+        let serial_circuit = fs::read(&file_to_load_choice).unwrap();
+        let mut loaded_circuit: Circuit = serde_json::from_slice(&serial_circuit).unwrap();
+
+        if !prepare_loaded_circuit(&mut loaded_circuit, &file_to_load_choice) {
+            return Circuit::new();
+        }
 
         loaded_circuit
     } else {
@@ -420,6 +1494,267 @@ fn load() -> Circuit {
     }
 }
 
+fn load_session() -> Option<Simulator> {
+    let path = choose_path("Session to load:")?;
+
+    let mut session: Session = match fs::read(&path).map(|serialized| serde_json::from_slice(&serialized)) {
+        Ok(Ok(session)) => session,
+        Ok(Err(error)) => {
+            println!("Error while parsing session: {error}");
+            return None;
+        },
+        Err(error) => {
+            println!("Error while loading session: {error}");
+            return None;
+        },
+    };
+
+    if !prepare_loaded_circuit(&mut session.circuit, &path) {
+        return None;
+    }
+
+    match Simulator::from_session(session) {
+        Ok(simulator) => {
+            println!("Session has been loaded at time {} after {} steps.", simulator.time(), simulator.step_count());
+            Some(simulator)
+        },
+        Err(error) => {
+            println!("Error while restoring session: {error}");
+            None
+        },
+    }
+}
+
+/// Resolves the subcircuits and loads the ROM images of a circuit loaded from
+/// `path`. Returns false if that failed.
+fn prepare_loaded_circuit(circuit: &mut Circuit, path: &str) -> bool {
+    if circuit.has_unresolved_subcircuits() {
+        println!("The circuit references subcircuits that have to be loaded from a library.");
+
+        if let Err(error) = load_library().resolve(circuit) {
+            println!("Error while resolving subcircuits: {error}");
+            return false;
+        }
+    }
+
+    let base_directory = Path::new(path).parent().unwrap_or(Path::new(""));
+    if let Err(error) = memory::load_roms(circuit, base_directory) {
+        println!("Error while loading ROM images: {error}");
+        return false;
+    }
+
+    if let Err(error) = circuit.validate() {
+        println!("The circuit cannot be simulated: {error}");
+        return false;
+    }
+
+    true
+}
+
+fn load_library() -> Library {
+    let current_dir = std::env::current_dir().unwrap();
+    let help_message = format!("Current directory: {}, leave empty to finish", current_dir.to_string_lossy());
+
+    let mut library = Library::new();
+
+    loop {
+        let file_to_load_answer = Text::new("Library file to load:")
+            .with_autocomplete(cli_util::FilePathCompleter::default())
+            .with_help_message(&help_message)
+            .prompt();
+
+        match file_to_load_answer {
+            Ok(file_to_load_choice) if !file_to_load_choice.is_empty() => {
+                match library.load_file(&file_to_load_choice) {
+                    Ok(()) => println!("Library file {file_to_load_choice} has been loaded."),
+                    Err(error) => println!("Error while loading library file: {error}"),
+                }
+            },
+            Ok(_) => break,
+            Err(_) => {
+                simple_error();
+                break;
+            },
+        }
+    }
+
+    library
+}
+
+fn choose_count(message: &str) -> Option<usize> {
+    let count_answer = CustomType::<usize>::new(message)
+        .with_validator(|&count: &usize| {
+            if count == 0 || count > 16 {
+                Ok(Validation::Invalid("The number has to be between 1 and 16.".into()))
+            } else {
+                Ok(Validation::Valid)
+            }
+        })
+        .prompt();
+
+    match count_answer {
+        Ok(count_choice) => Some(count_choice),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
+fn choose_register() -> Option<Function> {
+    let width = choose_count("How many bits wide should the register be?")?;
+    let options = choose_flip_flop_options()?;
+    Some(Function::Register { width, options })
+}
+
+fn choose_counter() -> Option<Function> {
+    let width = choose_count("How many bits wide should the counter be?")?;
+    let count_limit = u32::try_from(width).ok().and_then(|width| 1usize.checked_shl(width)).unwrap_or(usize::MAX);
+
+    let modulus_answer = CustomType::<usize>::new("At which count should the counter wrap around?")
+        .with_default(count_limit)
+        .with_validator(move |&modulus: &usize| {
+            if modulus == 0 || modulus > count_limit {
+                Ok(Validation::Invalid(format!("The modulus has to be between 1 and {count_limit}.").into()))
+            } else {
+                Ok(Validation::Valid)
+            }
+        })
+        .prompt();
+
+    let Ok(modulus) = modulus_answer else {
+        simple_error();
+        return None;
+    };
+
+    let edge = choose_clock_edge()?;
+    Some(Function::Counter { width, modulus, edge })
+}
+
+fn choose_shift_register() -> Option<(usize, ClockEdge)> {
+    let length = choose_count("How many bits long should the shift register be?")?;
+    let edge = choose_clock_edge()?;
+    Some((length, edge))
+}
+
+fn choose_ram() -> Option<Function> {
+    let address_width = choose_count("How many address bits should the RAM have?")?;
+    let data_width = choose_count("How many bits wide should a word be?")?;
+    let edge = choose_clock_edge()?;
+    Some(Function::Ram { address_width, data_width, edge })
+}
+
+fn choose_clock_edge() -> Option<ClockEdge> {
+    match Select::new("On which clock edges should it trigger?", vec![ClockEdge::Rising, ClockEdge::Falling, ClockEdge::Dual]).prompt() {
+        Ok(edge) => Some(edge),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
+fn choose_flip_flop() -> Option<Function> {
+    let kind_options = vec![
+        FlipFlopKind::JK,
+        FlipFlopKind::D,
+        FlipFlopKind::T,
+    ];
+
+    let kind_answer = Select::new("Which kind of flip flop should be used?", kind_options).prompt();
+
+    match kind_answer {
+        Ok(kind) => choose_flip_flop_options().map(|options| Function::FlipFlop { kind, options }),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
+fn choose_flip_flop_options() -> Option<FlipFlopOptions> {
+    let Ok(enable) = Confirm::new("Should there be an enable input?").with_default(false).prompt() else {
+        simple_error();
+        return None;
+    };
+
+    let Ok(preset_clear) = Confirm::new("Should there be preset and clear inputs?").with_default(false).prompt() else {
+        simple_error();
+        return None;
+    };
+
+    let edge = choose_clock_edge()?;
+    Some(FlipFlopOptions { enable, preset_clear, edge })
+}
+
+fn choose_comparator() -> Option<Function> {
+    let width = choose_count("How many bits wide should the comparator be?")?;
+
+    match Confirm::new("Should the numbers be compared as signed?").with_default(false).prompt() {
+        Ok(signed) => Some(Function::Comparator { width, signed }),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
+fn choose_clock() -> Option<Function> {
+    let period_answer = CustomType::<NonZeroUsize>::new("How many ticks long is a clock period?").prompt();
+    let period = match period_answer {
+        Ok(period_choice) => period_choice,
+        Err(_) => {
+            simple_error();
+            return None;
+        },
+    };
+
+    let duty_cycle_answer = CustomType::<usize>::new("For how many ticks of a period should the clock be on?")
+        .with_validator(move |&duty_cycle: &usize| {
+            if duty_cycle > period.get() {
+                Ok(Validation::Invalid("The clock can not be on for longer than its period.".into()))
+            } else {
+                Ok(Validation::Valid)
+            }
+        })
+        .prompt();
+
+    match duty_cycle_answer {
+        Ok(duty_cycle) => Some(Function::Clock { period, duty_cycle }),
+        Err(_) => {
+            simple_error();
+            None
+        },
+    }
+}
+
+fn choose_subcircuit() -> Option<Function> {
+    let library = load_library();
+    let definitions: Vec<_> = library.all_definitions().map(|(name, version)| format!("{name}@{version}")).collect();
+
+    if definitions.is_empty() {
+        println!("The library does not contain any subcircuits.");
+        return None;
+    }
+
+    let definition_answer = Select::new("Which subcircuit should be used?", definitions).prompt();
+
+    if let Ok(definition_choice) = definition_answer {
+        let (name, version) = definition_choice.rsplit_once('@').unwrap();
+
+        match library.instantiate(name, version.parse().unwrap()) {
+            Ok(function) => Some(function),
+            Err(error) => {
+                println!("Error while resolving subcircuit: {error}");
+                None
+            },
+        }
+    } else {
+        simple_error();
+        None
+    }
+}
+
 fn simple_error() {
     println!("There was an error!");
 }
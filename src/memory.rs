@@ -0,0 +1,539 @@
+use std::{fmt::Display, fs, path::{Path, PathBuf}, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Circuit, function::{Function, saturating_power_of_two}};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemoryFormat {
+    /// Whitespace separated hexadecimal words, one word per address.
+    Hex,
+    /// Raw little endian words, each padded to whole bytes.
+    Binary,
+    /// Intel HEX records of the little endian bytes of the words, as in `Binary`.
+    IntelHex,
+    /// Logisim "v2.0 raw" images of hexadecimal words with `count*word` runs.
+    LogisimRaw,
+}
+
+/// Contents of a `Function::Rom` that are loaded from the referenced file.
+/// Only the path and format are serialized, see `load_roms`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RomImage {
+    path: PathBuf,
+    format: MemoryFormat,
+    #[serde(skip)]
+    words: Option<Arc<Vec<usize>>>,
+}
+
+#[derive(Debug)]
+pub enum MemoryError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+    /// A word would be stored at an address the memory does not have.
+    AddressOutOfRange { line: usize, address: usize },
+    /// The word at `address` has bits set beyond the lowest `data_width` bits.
+    WordTooWide { address: usize, word: usize, data_width: usize },
+    /// More words than the memory has addresses.
+    TooManyWords { count: usize, word_count: usize },
+    /// The component has no memory, see `Function::memory_layout`.
+    NotAMemory { component_index: usize },
+}
+
+/// Loads the images of all ROMs of the circuit, including the ones nested in
+/// other circuits. Relative paths start at `base_directory`. The ROMs of
+/// subcircuits are loaded together with their library, see `Library::load_file`.
+pub fn load_roms(circuit: &mut Circuit, base_directory: &Path) -> Result<(), MemoryError> {
+    for component in circuit.all_components_mut() {
+        load_function_roms(component.function_mut(), base_directory)?;
+    }
+
+    Ok(())
+}
+
+/// Like `load_roms` for a single function.
+pub fn load_function_roms(function: &mut Function, base_directory: &Path) -> Result<(), MemoryError> {
+    match function {
+        Function::Rom { address_width, data_width, image } => image.load(base_directory, *address_width, *data_width),
+        Function::Circuit(circuit) => load_roms(circuit, base_directory),
+        _ => Ok(()),
+    }
+}
+
+/// Loads the words of a memory with `word_count` words. Formats that place
+/// words at addresses fail for addresses beyond it instead of growing, and
+/// words wider than `data_width` fail in every format.
+pub fn load_file<P: AsRef<Path>>(path: P, format: MemoryFormat, word_count: usize, data_width: usize) -> Result<Vec<usize>, MemoryError> {
+    let bytes = fs::read(path).map_err(MemoryError::Io)?;
+
+    let words = match format {
+        MemoryFormat::Hex => parse_hex(&String::from_utf8_lossy(&bytes))?,
+        MemoryFormat::Binary => parse_binary(&bytes, data_width),
+        MemoryFormat::IntelHex => parse_intel_hex(&String::from_utf8_lossy(&bytes), word_count, data_width)?,
+        MemoryFormat::LogisimRaw => parse_logisim_raw(&String::from_utf8_lossy(&bytes), word_count)?,
+    };
+
+    check_word_widths(&words, data_width)?;
+    Ok(words)
+}
+
+/// Fails for the first word that does not fit into `data_width` bits.
+pub fn check_word_widths(words: &[usize], data_width: usize) -> Result<(), MemoryError> {
+    let fits = |word: usize| u32::try_from(data_width).ok().and_then(|width| word.checked_shr(width)).is_none_or(|rest| rest == 0);
+
+    match words.iter().position(|&word| !fits(word)) {
+        Some(address) => Err(MemoryError::WordTooWide { address, word: words[address], data_width }),
+        None => Ok(()),
+    }
+}
+
+pub fn save_file<P: AsRef<Path>>(path: P, format: MemoryFormat, data_width: usize, words: &[usize]) -> Result<(), MemoryError> {
+    let bytes = match format {
+        MemoryFormat::Hex => format_hex(words, data_width).into_bytes(),
+        MemoryFormat::Binary => format_binary(words, data_width),
+        MemoryFormat::IntelHex => format_intel_hex(words, data_width).into_bytes(),
+        MemoryFormat::LogisimRaw => format_logisim_raw(words, data_width).into_bytes(),
+    };
+
+    fs::write(path, bytes).map_err(MemoryError::Io)
+}
+
+/// Parses whitespace separated hexadecimal words. Everything after a `#` on a line is ignored.
+pub fn parse_hex(text: &str) -> Result<Vec<usize>, MemoryError> {
+    let mut words = Vec::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let content = line.split('#').next().unwrap_or_default();
+
+        for word in content.split_whitespace() {
+            let word = word.trim_start_matches("0x");
+            let value = usize::from_str_radix(word, 16).map_err(|error| MemoryError::Parse { line: line_index + 1, message: format!("{word}: {error}") })?;
+            words.push(value);
+        }
+    }
+
+    Ok(words)
+}
+
+/// Formats the words as hexadecimal numbers with eight words per line.
+pub fn format_hex(words: &[usize], data_width: usize) -> String {
+    let digits = data_width.div_ceil(4).max(1);
+
+    words.chunks(8)
+        .map(|line| line.iter().map(|word| format!("{word:0digits$x}")).collect::<Vec<_>>().join(" ") + "\n")
+        .collect()
+}
+
+pub fn parse_binary(bytes: &[u8], data_width: usize) -> Vec<usize> {
+    bytes.chunks(bytes_per_word(data_width))
+        .map(|word| word.iter().rev().fold(0, |acc, &byte| (acc << 8) | byte as usize))
+        .collect()
+}
+
+pub fn format_binary(words: &[usize], data_width: usize) -> Vec<u8> {
+    words.iter()
+        .flat_map(|&word| (0..bytes_per_word(data_width)).map(move |i| u32::try_from(8 * i).ok().and_then(|shift| word.checked_shr(shift)).unwrap_or(0) as u8))
+        .collect()
+}
+
+/// Parses data, extended segment address and extended linear address records
+/// for a memory with `word_count` words. Bytes that are not covered by any
+/// record are 0.
+pub fn parse_intel_hex(text: &str, word_count: usize, data_width: usize) -> Result<Vec<usize>, MemoryError> {
+    let byte_count = word_count.saturating_mul(bytes_per_word(data_width));
+    let mut bytes = Vec::new();
+    let mut base_address = 0;
+
+    for (line_index, line) in text.lines().enumerate() {
+        let parse_error = |message: &str| MemoryError::Parse { line: line_index + 1, message: message.to_owned() };
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let record = line.strip_prefix(':').ok_or_else(|| parse_error("record does not start with ':'"))?;
+
+        if record.len() % 2 != 0 || record.len() < 10 {
+            return Err(parse_error("record has an invalid length"));
+        }
+
+        let record = (0..record.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&record[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| parse_error(&error.to_string()))?;
+
+        let data_len = record[0] as usize;
+        if record.len() != data_len + 5 {
+            return Err(parse_error("record length does not match its byte count"));
+        }
+
+        if record.iter().fold(0u8, |acc, &byte| acc.wrapping_add(byte)) != 0 {
+            return Err(parse_error("checksum mismatch"));
+        }
+
+        let address = (record[1] as usize) << 8 | record[2] as usize;
+        let data = &record[4..4 + data_len];
+
+        match record[3] {
+            0x00 => {
+                let start = base_address + address;
+
+                if start + data_len > byte_count {
+                    return Err(MemoryError::AddressOutOfRange { line: line_index + 1, address: start.max(byte_count) / bytes_per_word(data_width) });
+                }
+
+                if bytes.len() < start + data_len {
+                    bytes.resize(start + data_len, 0);
+                }
+
+                bytes[start..start + data_len].copy_from_slice(data);
+            },
+            0x01 => break,
+            0x02 | 0x04 if data_len == 2 => {
+                let upper = (data[0] as usize) << 8 | data[1] as usize;
+                base_address = if record[3] == 0x02 { upper << 4 } else { upper << 16 };
+            },
+            0x02 | 0x04 => return Err(parse_error("extended address record does not have 2 data bytes")),
+            0x03 | 0x05 => (),
+            _ => return Err(parse_error("unsupported record type")),
+        }
+    }
+
+    Ok(parse_binary(&bytes, data_width))
+}
+
+/// Formats the bytes of the words as data records of 16 bytes, with extended
+/// linear address records where needed, followed by an end of file record.
+pub fn format_intel_hex(words: &[usize], data_width: usize) -> String {
+    let record = |address: usize, record_type: u8, data: &[u8]| {
+        let mut bytes = vec![data.len() as u8, (address >> 8) as u8, address as u8, record_type];
+        bytes.extend(data);
+        let checksum = bytes.iter().fold(0u8, |acc, &byte| acc.wrapping_add(byte)).wrapping_neg();
+        bytes.push(checksum);
+
+        format!(":{}\n", bytes.iter().map(|byte| format!("{byte:02X}")).collect::<String>())
+    };
+
+    let mut text = String::new();
+    let mut upper_address = 0;
+
+    for (i, data) in format_binary(words, data_width).chunks(16).enumerate() {
+        let address = i * 16;
+
+        if address >> 16 != upper_address {
+            upper_address = address >> 16;
+            text += &record(0, 0x04, &[(upper_address >> 8) as u8, upper_address as u8]);
+        }
+
+        text += &record(address & 0xffff, 0x00, data);
+    }
+
+    text + &record(0, 0x01, &[])
+}
+
+/// Parses a Logisim "v2.0 raw" image for a memory with `word_count` words,
+/// where `count*word` repeats a word.
+pub fn parse_logisim_raw(text: &str, word_count: usize) -> Result<Vec<usize>, MemoryError> {
+    let mut lines = text.lines().enumerate();
+
+    match lines.next() {
+        Some((_, header)) if header.trim() == "v2.0 raw" => (),
+        _ => return Err(MemoryError::Parse { line: 1, message: "missing \"v2.0 raw\" header".into() }),
+    }
+
+    let mut words = Vec::new();
+
+    for (line_index, line) in lines {
+        let parse_error = |message: String| MemoryError::Parse { line: line_index + 1, message };
+        let content = line.split('#').next().unwrap_or_default();
+
+        for entry in content.split_whitespace() {
+            let (count, word) = match entry.split_once('*') {
+                Some((count, word)) => (count.parse().map_err(|error| parse_error(format!("{count}: {error}")))?, word),
+                None => (1, entry),
+            };
+
+            let word = usize::from_str_radix(word, 16).map_err(|error| parse_error(format!("{word}: {error}")))?;
+
+            if count > word_count - words.len() {
+                return Err(MemoryError::AddressOutOfRange { line: line_index + 1, address: word_count });
+            }
+
+            words.extend(std::iter::repeat_n(word, count));
+        }
+    }
+
+    Ok(words)
+}
+
+/// Formats the words as a Logisim "v2.0 raw" image with runs of equal words combined.
+pub fn format_logisim_raw(words: &[usize], data_width: usize) -> String {
+    let digits = data_width.div_ceil(4).max(1);
+    let mut entries = Vec::new();
+    let mut i = 0;
+
+    while i < words.len() {
+        let count = words[i..].iter().take_while(|&&word| word == words[i]).count();
+
+        if count > 1 {
+            entries.push(format!("{count}*{:0digits$x}", words[i]));
+        } else {
+            entries.push(format!("{:0digits$x}", words[i]));
+        }
+
+        i += count;
+    }
+
+    let lines: String = entries.chunks(8).map(|line| line.join(" ") + "\n").collect();
+    format!("v2.0 raw\n{lines}")
+}
+
+fn bytes_per_word(data_width: usize) -> usize {
+    data_width.div_ceil(8).max(1)
+}
+
+impl RomImage {
+    pub fn new(path: PathBuf, format: MemoryFormat) -> Self {
+        Self {
+            path,
+            format,
+            words: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> MemoryFormat {
+        self.format
+    }
+
+    pub fn words(&self) -> Option<&[usize]> {
+        self.words.as_deref().map(Vec::as_slice)
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.words.is_some()
+    }
+
+    /// Sets the words directly instead of loading them from the file.
+    pub fn set_words(&mut self, words: Vec<usize>) {
+        self.words = Some(Arc::new(words));
+    }
+
+    pub fn load<P: AsRef<Path>>(&mut self, base_directory: P, address_width: usize, data_width: usize) -> Result<(), MemoryError> {
+        let words = load_file(base_directory.as_ref().join(&self.path), self.format, saturating_power_of_two(address_width), data_width)?;
+        self.set_words(words);
+        Ok(())
+    }
+
+    /// The loaded words, where an image that has not been loaded has none and
+    /// so reads as 0 at every address. `load_roms` reports images that cannot
+    /// be loaded.
+    pub(crate) fn loaded_words(&self) -> &[usize] {
+        self.words().unwrap_or_default()
+    }
+}
+
+impl Display for MemoryFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryError::Io(error) => write!(f, "io error: {error}"),
+            MemoryError::Parse { line, message } => write!(f, "parse error in line {line}: {message}"),
+            MemoryError::AddressOutOfRange { line, address } => write!(f, "address {address:#x} in line {line} is outside of the memory"),
+            MemoryError::WordTooWide { address, word, data_width } => write!(f, "word {word:#x} at address {address:#x} is wider than {data_width} bits"),
+            MemoryError::TooManyWords { count, word_count } => write!(f, "{count} words do not fit into a memory of {word_count} words"),
+            MemoryError::NotAMemory { component_index } => write!(f, "component {component_index} has no memory"),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Value, function::to_values, library::Library};
+
+    use super::*;
+
+    #[test]
+    fn rom_image_referenced_by_circuit() {
+        let directory = std::env::temp_dir().join(format!("circuitsim_rom_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        save_file(directory.join("rom.hex"), MemoryFormat::IntelHex, 4, &[0x1, 0x2, 0x4, 0x8]).unwrap();
+
+        let mut circuit = Circuit::new();
+        let (_, a0) = circuit.add_input();
+        let (_, a1) = circuit.add_input();
+        let rom = Function::Rom { address_width: 2, data_width: 4, image: RomImage::new("rom.hex".into(), MemoryFormat::IntelHex) };
+        let (_, outputs) = circuit.add_component(rom, vec![a0, a1]).unwrap();
+        outputs.iter().for_each(|&value_index| { circuit.add_output(value_index); });
+
+        let serialized = serde_json::to_string(&circuit).unwrap();
+        assert!(serialized.contains("rom.hex"));
+
+        let mut loaded: Circuit = serde_json::from_str(&serialized).unwrap();
+        load_roms(&mut loaded, &directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        let function = Function::Circuit(loaded);
+        for address in 0..4 {
+            assert_eq!(function.evaluate(&to_values(address, 2), &[]), (to_values(1 << address, 4), vec![]));
+        }
+
+        assert_eq!(function.evaluate(&[Value::On, Value::On], &[]).0, [Value::Off, Value::Off, Value::Off, Value::On]);
+    }
+
+    #[test]
+    fn unloaded_rom_image_reads_as_zero() {
+        let rom = Function::Rom { address_width: 2, data_width: 4, image: RomImage::new("missing.hex".into(), MemoryFormat::Hex) };
+        assert_eq!(rom.evaluate(&[Value::On, Value::Off], &[]), (vec![Value::Off; 4], vec![]));
+
+        let mut circuit = Circuit::new();
+        let (_, a0) = circuit.add_input();
+        let (_, a1) = circuit.add_input();
+        circuit.add_component(rom, vec![a0, a1]).unwrap();
+        assert!(matches!(load_roms(&mut circuit, &std::env::temp_dir().join("circuitsim_missing_rom")), Err(MemoryError::Io(_))));
+    }
+
+    #[test]
+    fn rom_images_of_libraries_are_relative_to_the_library() {
+        let directory = std::env::temp_dir().join(format!("circuitsim_library_rom_{}", std::process::id()));
+        let library_directory = directory.join("library");
+        fs::create_dir_all(&library_directory).unwrap();
+        save_file(library_directory.join("rom.hex"), MemoryFormat::Hex, 4, &[0x1, 0x2, 0x4, 0x8]).unwrap();
+
+        let mut definition = Circuit::new();
+        let (_, a0) = definition.add_input();
+        let (_, a1) = definition.add_input();
+        let rom = Function::Rom { address_width: 2, data_width: 4, image: RomImage::new("rom.hex".into(), MemoryFormat::Hex) };
+        let (_, outputs) = definition.add_component(rom, vec![a0, a1]).unwrap();
+        outputs.iter().for_each(|&value_index| { definition.add_output(value_index); });
+
+        let mut library = Library::new();
+        library.add("rom".into(), 1, definition).unwrap();
+        library.save_file(library_directory.join("library.json")).unwrap();
+
+        let mut library = Library::new();
+        library.load_file(library_directory.join("library.json")).unwrap();
+
+        // the main circuit is in another directory without the image
+        let mut circuit = Circuit::new();
+        let (_, a0) = circuit.add_input();
+        let (_, a1) = circuit.add_input();
+        let (_, outputs) = circuit.add_component(library.instantiate("rom", 1).unwrap(), vec![a0, a1]).unwrap();
+        outputs.iter().for_each(|&value_index| { circuit.add_output(value_index); });
+        load_roms(&mut circuit, &directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        let function = Function::Circuit(circuit);
+        assert_eq!(function.evaluate(&to_values(2, 2), &[]), (to_values(0x4, 4), vec![]));
+    }
+
+    #[test]
+    fn hex() {
+        let text = "00 1f 0x2\n# comment\n  ff # trailing comment\n";
+        assert_eq!(parse_hex(text).unwrap(), vec![0x00, 0x1f, 0x02, 0xff]);
+
+        let words: Vec<_> = (0..10).collect();
+        let text = format_hex(&words, 8);
+        assert_eq!(text, "00 01 02 03 04 05 06 07\n08 09\n");
+        assert_eq!(parse_hex(&text).unwrap(), words);
+
+        assert!(matches!(parse_hex("00\n0g"), Err(MemoryError::Parse { line: 2, .. })));
+    }
+
+    #[test]
+    fn words_wider_than_data_width() {
+        assert!(check_word_widths(&[0x0, 0xf], 4).is_ok());
+        assert!(matches!(check_word_widths(&[0x0, 0x1f], 4), Err(MemoryError::WordTooWide { address: 1, word: 0x1f, data_width: 4 })));
+        assert!(check_word_widths(&[usize::MAX], usize::BITS as usize).is_ok());
+
+        let path = std::env::temp_dir().join(format!("circuitsim_wide_{}.hex", std::process::id()));
+        fs::write(&path, "1 2 1ff\n").unwrap();
+        let result = load_file(&path, MemoryFormat::Hex, 4, 8);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(MemoryError::WordTooWide { address: 2, word: 0x1ff, data_width: 8 })));
+    }
+
+    #[test]
+    fn intel_hex() {
+        let text = ":0300300002337A1E\n:00000001FF\n";
+        let words = parse_intel_hex(text, 0x100, 8).unwrap();
+        assert_eq!(words.len(), 0x33);
+        assert_eq!(words[0x30..], [0x02, 0x33, 0x7a]);
+        assert!(words[..0x30].iter().all(|&word| word == 0));
+
+        // words wider than a byte are little endian
+        let words: Vec<_> = (0..40).map(|i| i * 0x0101).collect();
+        let text = format_intel_hex(&words, 16);
+        assert!(text.starts_with(":1000000000000101020203030404050506060707"));
+        assert!(text.ends_with(":00000001FF\n"));
+        assert_eq!(parse_intel_hex(&text, 0x100, 16).unwrap(), words);
+
+        // extended linear addresses beyond 64 KiB
+        let words: Vec<_> = (0..0x10010).map(|i| i % 251).collect();
+        let text = format_intel_hex(&words, 8);
+        assert!(text.contains(":020000040001F9\n"));
+        assert_eq!(parse_intel_hex(&text, 0x20000, 8).unwrap(), words);
+
+        // extended segment addresses
+        let words = parse_intel_hex(":020000021000EC\n:0100000055AA\n:00000001FF\n", 0x20000, 8).unwrap();
+        assert_eq!(words.len(), 0x10001);
+        assert_eq!(words[0x10000], 0x55);
+
+        assert!(matches!(parse_intel_hex(":0300300002337A1F\n", 0x100, 8), Err(MemoryError::Parse { line: 1, .. })));
+        assert!(parse_intel_hex(":00000001FF\n0300", 0x100, 8).is_ok());
+        assert!(matches!(parse_intel_hex("\n0300300002337A1E", 0x100, 8), Err(MemoryError::Parse { line: 2, .. })));
+
+        // addresses beyond the memory fail before anything is allocated for them
+        let text = ":020000040800F2\n:0100000055AA\n:00000001FF\n";
+        assert!(matches!(parse_intel_hex(text, 0x100, 8), Err(MemoryError::AddressOutOfRange { line: 2, address: 0x0800_0000 })));
+        assert!(matches!(parse_intel_hex(":0300FE0002337A50\n", 0x100, 8), Err(MemoryError::AddressOutOfRange { line: 1, address: 0x100 })));
+        assert!(matches!(parse_intel_hex(":0200FF000233CA\n", 0x80, 16), Err(MemoryError::AddressOutOfRange { line: 1, address: 0x80 })));
+        assert_eq!(parse_intel_hex(":0300FD0002337A51\n", 0x100, 8).unwrap()[0xfd..], [0x02, 0x33, 0x7a]);
+
+        // extended address records need exactly two data bytes
+        match parse_intel_hex(":0100000410EB\n", 0x100, 8) {
+            Err(MemoryError::Parse { line: 1, message }) => assert!(message.contains("2 data bytes")),
+            result => panic!("expected a length error but got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn logisim_raw() {
+        let text = "v2.0 raw\n0 1 2*f # comment\n3*a\n";
+        assert_eq!(parse_logisim_raw(text, 0x100).unwrap(), vec![0x0, 0x1, 0xf, 0xf, 0xa, 0xa, 0xa]);
+
+        let words = vec![0x0, 0x1, 0xf, 0xf, 0xa, 0xa, 0xa];
+        let text = format_logisim_raw(&words, 4);
+        assert_eq!(text, "v2.0 raw\n0 1 2*f 3*a\n");
+        assert_eq!(parse_logisim_raw(&text, 0x100).unwrap(), words);
+
+        assert!(matches!(parse_logisim_raw("0 1 2", 0x100), Err(MemoryError::Parse { line: 1, .. })));
+        assert!(matches!(parse_logisim_raw("v2.0 raw\nx*1", 0x100), Err(MemoryError::Parse { line: 2, .. })));
+        assert!(matches!(parse_logisim_raw("v2.0 raw\n0\n99999999999*1", 0x100), Err(MemoryError::AddressOutOfRange { line: 3, address: 0x100 })));
+        assert_eq!(parse_logisim_raw("v2.0 raw\n0 3*1", 4).unwrap(), [0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn binary() {
+        assert_eq!(parse_binary(&[0x01, 0x02, 0x03], 8), vec![0x01, 0x02, 0x03]);
+        assert_eq!(parse_binary(&[0x34, 0x12, 0xff], 12), vec![0x1234, 0xff]);
+
+        let words = vec![0x0123, 0x0abc, 0x0fff];
+        assert_eq!(format_binary(&words, 12), vec![0x23, 0x01, 0xbc, 0x0a, 0xff, 0x0f]);
+        assert_eq!(parse_binary(&format_binary(&words, 12), 12), words);
+
+        // words wider than a usize are padded with zero bytes
+        assert_eq!(format_binary(&[0x0102], 72), [vec![0x02, 0x01], vec![0x00; 7]].concat());
+    }
+}
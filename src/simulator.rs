@@ -1,38 +1,376 @@
-use std::{collections::VecDeque, num::NonZeroUsize};
+use std::{collections::{BTreeSet, HashMap, VecDeque}, fmt::Display, num::NonZeroUsize};
 
-use crate::{Value, Circuit, function::Function, element::Output};
+use serde::{Deserialize, Serialize};
 
+use crate::{Value, Circuit, CircuitError, function::{Function, to_number, to_values}, element::{Output, BusResolution}, memory::{self, MemoryError}, truth_table::TruthTable};
+
+#[derive(Clone, Debug)]
 pub struct Simulator {
     circuit: Circuit,
     values: Vec<Value>,
     owned_values: Vec<Value>,
     changed_values: VecDeque<usize>,
-    steps_until_unstable: NonZeroUsize,
+    time: usize,
+    bus_contentions: BTreeSet<usize>,
+    config: SimulatorConfig,
+    random_state: u64,
+    step_count: usize,
+    trace: Vec<TraceEvent>,
+    history: VecDeque<HistoryEntry>,
+    recording: Option<HistoryEntry>,
+    breakpoints: Vec<Breakpoint>,
+    breakpoint_hit: Option<BreakpointHit>,
+    causes: HashMap<usize, Cause>,
+    evaluation_trigger: Option<usize>,
+    /// Set for the throwaway simulators of subcircuits, which nobody asks
+    /// for causes.
+    skip_causes: bool,
+}
+
+/// Settings of a `Simulator`, see `Simulator::with_config`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulatorConfig {
+    /// Number of steps after which `simulate` reports an oscillation.
+    pub steps_until_unstable: NonZeroUsize,
+    pub initial_values: InitialValues,
+    pub event_order: EventOrder,
+    pub trace: TraceOptions,
+    /// Number of operations like steps, ticks and input changes that can be
+    /// undone, see `Simulator::step_back`.
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+}
+
+fn default_history_limit() -> usize {
+    1000
+}
+
+/// The values of a new simulator before the first simulation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InitialValues {
+    #[default]
+    AllOff,
+    AllOn,
+    /// Random values that are the same for the same seed.
+    Random { seed: u64 },
+    /// The value for every value index, where missing ones are `Off`.
+    Explicit(Vec<Value>),
+}
+
+/// The order in which changed values are propagated by `Simulator::step`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventOrder {
+    /// The value that changed first is propagated first.
+    #[default]
+    Fifo,
+    /// The value that changed last is propagated first.
+    Lifo,
+    /// A random changed value is propagated, the same order for the same seed.
+    Random { seed: u64 },
+}
+
+/// What the simulator records in its trace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceOptions {
+    pub values: bool,
+    pub components: bool,
+}
+
+/// What an operation on the simulator overwrote, so that it can be undone.
+/// Only the changes are kept rather than copies of all values.
+#[derive(Clone, Debug, Default)]
+struct HistoryEntry {
+    step_count: usize,
+    time: usize,
+    random_state: u64,
+    /// Position in the queue and value index of the propagated value.
+    popped_value: Option<(usize, usize)>,
+    pushed_value_count: usize,
+    old_values: Vec<(usize, Value)>,
+    old_owned_values: Vec<(usize, Value)>,
+    old_bus_contentions: Vec<(usize, bool)>,
+    old_causes: Vec<(usize, Option<Cause>)>,
+}
+
+impl HistoryEntry {
+    /// The step count after the operation.
+    fn steps_after(&self) -> usize {
+        self.step_count + usize::from(self.popped_value.is_some())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TraceEvent {
+    ValueChanged { step: usize, value_index: usize, value: Value },
+    ComponentEvaluated { step: usize, component_index: usize },
+}
+
+/// The event that made a value change most recently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cause {
+    pub step: usize,
+    pub source: CauseSource,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CauseSource {
+    /// The value was set with `Simulator::set_input`.
+    Input { input_index: usize },
+    /// The value is an output of a component that was evaluated because the
+    /// value `trigger` changed. Without a trigger the component was evaluated
+    /// by a tick or an edit like `Simulator::set_memory`.
+    Component { component_index: usize, trigger: Option<usize> },
+    /// The value is a bus that was resolved again after an edit like
+    /// `Simulator::set_bus_resolution`.
+    Edit,
+}
+
+/// Why a value has its current state, see `Simulator::explain`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub value_index: usize,
+    pub value: Value,
+    /// `None` if the value has not changed since the simulation started.
+    pub cause: Option<Cause>,
+    /// Why the trigger of the cause had its state at that time, if it has
+    /// not changed again since.
+    pub trigger: Option<Box<Explanation>>,
+}
+
+/// A condition that stops `Simulator::simulate` when it occurs during a step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Breakpoint {
+    ValueBecomes { value_index: usize, value: Value },
+    ComponentEvaluated { component_index: usize },
+    OutputChanges { output_index: usize },
+}
+
+/// The first breakpoint that occurred during a step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BreakpointHit {
+    pub breakpoint_index: usize,
+    pub breakpoint: Breakpoint,
+    pub step: usize,
+}
+
+/// Why a simulation stopped before it became stable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Interruption {
+    Oscillation(Oscillation),
+    Breakpoint(BreakpointHit),
+}
+
+/// Report of a simulation that did not become stable within the step limit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Oscillation {
+    /// Number of steps after which the state of the simulator repeats, or
+    /// `None` if no repetition was found within another step limit.
+    pub period: Option<usize>,
+    /// Value indices that change during the repeating cycle.
+    pub toggling_values: BTreeSet<usize>,
+    /// Components with toggling inputs and outputs, which form the feedback loop.
+    pub feedback_components: BTreeSet<usize>,
+}
+
+/// The state of a `Simulator` apart from its circuit and config, which
+/// includes the stored bits of flip-flops and memories. See `Simulator::snapshot`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    values: Vec<Value>,
+    owned_values: Vec<Value>,
+    changed_values: VecDeque<usize>,
+    time: usize,
+    bus_contentions: BTreeSet<usize>,
+    random_state: u64,
+    step_count: usize,
+}
+
+/// Everything needed to continue a simulation later, see `Simulator::session`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub circuit: Circuit,
+    pub config: SimulatorConfig,
+    pub snapshot: Snapshot,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot was taken of a circuit with a different number of values
+    /// or owned values.
+    CircuitMismatch,
+    InvalidCircuit(CircuitError),
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        SimulatorConfig {
+            steps_until_unstable: NonZeroUsize::new(1000).unwrap(),
+            initial_values: InitialValues::default(),
+            event_order: EventOrder::default(),
+            trace: TraceOptions::default(),
+            history_limit: default_history_limit(),
+        }
+    }
+}
 
 impl Simulator {
-    pub fn new(circuit: Circuit) -> Self {
+    /// Creates a simulator for a circuit whose subcircuits are resolved, see
+    /// `Circuit::validate`.
+    pub fn new(circuit: Circuit) -> Result<Self, CircuitError> {
+        Self::with_config(circuit, SimulatorConfig::default())
+    }
+
+    pub fn with_config(circuit: Circuit, config: SimulatorConfig) -> Result<Self, CircuitError> {
+        circuit.validate()?;
+        Ok(Self::new_unchecked(circuit, config))
+    }
+
+    /// Creates a simulator for a circuit that is known to be valid.
+    fn new_unchecked(circuit: Circuit, config: SimulatorConfig) -> Self {
         let value_list_len = circuit.value_list_len();
         let owned_value_list_len = circuit.owned_value_list_len();
         let all_value_indices: VecDeque<usize> = (0..value_list_len).collect();
 
+        let values = match &config.initial_values {
+            InitialValues::AllOff => vec![Value::Off; value_list_len],
+            InitialValues::AllOn => vec![Value::On; value_list_len],
+            InitialValues::Random { seed } => {
+                let mut random_state = *seed;
+                (0..value_list_len).map(|_| if next_random(&mut random_state) & 1 == 1 { Value::On } else { Value::Off }).collect()
+            },
+            InitialValues::Explicit(values) => (0..value_list_len).map(|i| values.get(i).copied().unwrap_or(Value::Off)).collect(),
+        };
+
+        let random_state = match config.event_order {
+            EventOrder::Random { seed } => seed,
+            _ => 0,
+        };
+
+        let mut owned_values = vec![Value::Off; owned_value_list_len];
+        for component in circuit.all_components() {
+            for (&value_index, &value) in component.owned_value_indices().iter().zip(component.initial_state()) {
+                owned_values[value_index] = value;
+            }
+        }
 
-        Self {
-            circuit: circuit,
-            values: vec![Value::Off; value_list_len],
-            owned_values: vec![Value::Off; owned_value_list_len],
+        let mut simulator = Self {
+            circuit,
+            values,
+            owned_values,
             changed_values: all_value_indices,
-            steps_until_unstable: NonZeroUsize::new(1000).unwrap(),
+            time: 0,
+            bus_contentions: BTreeSet::new(),
+            config,
+            random_state,
+            step_count: 0,
+            trace: vec![],
+            history: VecDeque::new(),
+            recording: None,
+            breakpoints: vec![],
+            breakpoint_hit: None,
+            causes: HashMap::new(),
+            evaluation_trigger: None,
+            skip_causes: false,
+        };
+
+        for component_index in 0..simulator.circuit.all_components().len() {
+            simulator.initialize_source(component_index);
         }
+
+        let bus_value_indices: Vec<usize> = simulator.circuit.all_buses().iter().map(|bus| bus.value_index()).collect();
+        for value_index in bus_value_indices {
+            simulator.refresh_bus(value_index);
+        }
+
+        // the power-on state is where the simulation starts, so nothing caused it
+        simulator.causes.clear();
+
+        simulator
+    }
+
+    /// Creates a simulator that only evaluates a valid circuit once, without a
+    /// history or causes.
+    pub(crate) fn for_evaluation(circuit: Circuit) -> Self {
+        let config = SimulatorConfig { history_limit: 0, ..SimulatorConfig::default() };
+        let mut simulator = Self::new_unchecked(circuit, config);
+        simulator.skip_causes = true;
+        simulator
+    }
+
+    /// Continues a saved session. The circuit of the session needs to have its
+    /// subcircuits resolved and ROM images loaded.
+    pub fn from_session(session: Session) -> Result<Self, SnapshotError> {
+        let mut simulator = Self::with_config(session.circuit, session.config).map_err(SnapshotError::InvalidCircuit)?;
+        simulator.restore(&session.snapshot)?;
+        Ok(simulator)
+    }
+
+    /// Returns to the state the simulator was created in, including the power-on
+    /// state of components and the initial queue of changed values. Breakpoints
+    /// are kept while the history and trace are cleared.
+    ///
+    /// Memory written with `set_memory` is state like every other owned value
+    /// and is cleared, unless it is part of the initial state of the component.
+    /// Tables replaced with `set_truth_table` are part of the circuit and kept.
+    pub fn reset(&mut self) {
+        let breakpoints = std::mem::take(&mut self.breakpoints);
+        let skip_causes = self.skip_causes;
+        *self = Self::new_unchecked(self.circuit.clone(), self.config.clone());
+        self.breakpoints = breakpoints;
+        self.skip_causes = skip_causes;
+    }
+
+    pub fn session(&self) -> Session {
+        Session { circuit: self.circuit.clone(), config: self.config.clone(), snapshot: self.snapshot() }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            values: self.values.clone(),
+            owned_values: self.owned_values.clone(),
+            changed_values: self.changed_values.clone(),
+            time: self.time,
+            bus_contentions: self.bus_contentions.clone(),
+            random_state: self.random_state,
+            step_count: self.step_count,
+        }
+    }
+
+    /// Returns the simulator to the state of the snapshot, which has to be
+    /// taken of a simulator of the same circuit.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        let value_indices_valid = snapshot.changed_values.iter().chain(&snapshot.bus_contentions).all(|&value_index| value_index < self.values.len());
+
+        if snapshot.values.len() != self.values.len() || snapshot.owned_values.len() != self.owned_values.len() || !value_indices_valid {
+            return Err(SnapshotError::CircuitMismatch);
+        }
+
+        self.values.clone_from(&snapshot.values);
+        self.owned_values.clone_from(&snapshot.owned_values);
+        self.changed_values.clone_from(&snapshot.changed_values);
+        self.time = snapshot.time;
+        self.bus_contentions.clone_from(&snapshot.bus_contentions);
+        self.random_state = snapshot.random_state;
+        self.step_count = snapshot.step_count;
+        self.history.clear();
+        self.causes.clear();
+
+        Ok(())
     }
 
     pub fn set_input(&mut self, input_index: usize, value: Value) {
         let value_index = self.circuit.input(input_index).value_index();
 
         if self.values[value_index] != value {
-            self.values[value_index] = value;
-            self.changed_values.push_back(value_index);
+            self.record(|simulator| {
+                simulator.set_value(value_index, value);
+                simulator.set_cause(value_index, Some(Cause { step: simulator.step_count, source: CauseSource::Input { input_index } }));
+                simulator.queue_changed_value(value_index);
+            });
+
+            if self.config.trace.values {
+                self.trace.push(TraceEvent::ValueChanged { step: self.step_count, value_index, value });
+            }
         }
     }
 
@@ -49,6 +387,7 @@ impl Simulator {
     pub fn add_input(&mut self) -> (usize, usize) {
         let (input_index, value_index) = self.circuit.add_input();
         self.changed_values.push_back(value_index);
+        self.history.clear();
 
         while self.values.len() < self.circuit.value_list_len {
             self.values.push(Value::Off);
@@ -61,8 +400,9 @@ impl Simulator {
         self.circuit.add_output(value_index)
     }
 
-    pub fn add_component(&mut self, function: Function, input_value_indices: Vec<usize>) -> (usize, Vec<usize>) {
-        let (component_index, output_value_indices) = self.circuit.add_component(function, input_value_indices);
+    pub fn add_component(&mut self, function: Function, input_value_indices: Vec<usize>) -> Result<(usize, Vec<usize>), CircuitError> {
+        let (component_index, output_value_indices) = self.circuit.add_component(function, input_value_indices)?;
+        self.history.clear();
 
         while self.values.len() < self.circuit.value_list_len {
             self.values.push(Value::Off);
@@ -72,7 +412,44 @@ impl Simulator {
             self.owned_values.push(Value::Off);
         }
 
-        (component_index, output_value_indices)
+        self.initialize_source(component_index);
+
+        Ok((component_index, output_value_indices))
+    }
+
+    pub fn add_bus(&mut self, width: usize, resolution: BusResolution, pull: Option<Value>) -> Vec<usize> {
+        let bus_value_indices = self.circuit.add_bus(width, resolution, pull);
+        self.history.clear();
+
+        while self.values.len() < self.circuit.value_list_len {
+            self.values.push(Value::Off);
+        }
+
+        for &value_index in &bus_value_indices {
+            self.refresh_bus(value_index);
+        }
+
+        bus_value_indices
+    }
+
+    pub fn set_bus_resolution(&mut self, value_index: usize, resolution: BusResolution, pull: Option<Value>) -> Result<(), CircuitError> {
+        self.circuit.set_bus_resolution(value_index, resolution, pull)?;
+        self.history.clear();
+        self.refresh_bus(value_index);
+        Ok(())
+    }
+
+    pub fn add_bus_driver(&mut self, function: Function, input_value_indices: Vec<usize>, bus_value_indices: Vec<usize>) -> Result<usize, CircuitError> {
+        let component_index = self.circuit.add_bus_driver(function, input_value_indices, bus_value_indices)?;
+        self.history.clear();
+
+        while self.owned_values.len() < self.circuit.owned_value_list_len {
+            self.owned_values.push(Value::Off);
+        }
+
+        self.evaluate_component(component_index);
+
+        Ok(component_index)
     }
 
     pub fn circuit(&self) -> &Circuit {
@@ -91,62 +468,611 @@ impl Simulator {
         self.values[value]
     }
 
-    pub fn step(&mut self) {
-        if let Some(value_to_check) = self.changed_values.pop_front() {
-            let components_to_update = self.find_components_by_input(value_to_check);
+    /// Returns the words stored in a memory component like `Function::Ram` or
+    /// `Function::Rom` and `None` if the component has no memory. Words of a
+    /// RAM are owned values, so `session` saves them with the rest of the state.
+    pub fn memory(&self, component_index: usize) -> Option<Vec<usize>> {
+        let component = self.circuit.component(component_index);
 
-            for component_index in components_to_update {
-                let component = self.circuit.component(component_index);
-                let input_values: Vec<Value> = component.input_value_indices().iter()
-                    .map(|&value_index| self.values[value_index])
-                    .collect();
-                let old_output_values: Vec<Value> = component.output_value_indices().iter()
-                    .map(|&value_index| self.values[value_index])
+        if let Function::Rom { image, .. } = component.function() {
+            return image.words().map(<[usize]>::to_vec);
+        }
+
+        let (word_count, data_width) = component.function().memory_layout()?;
+
+        let words = (0..word_count)
+            .map(|word| {
+                let values: Vec<_> = component.owned_value_indices()[word * data_width..(word + 1) * data_width].iter()
+                    .map(|&value_index| self.owned_values[value_index])
                     .collect();
+                to_number(&values)
+            })
+            .collect();
+
+        Some(words)
+    }
+
+    /// Overwrites the words stored in a memory component starting at address 0.
+    /// Fails with `MemoryError::NotAMemory` if the component has no memory,
+    /// `MemoryError::TooManyWords` if there are more words than addresses and
+    /// `MemoryError::WordTooWide` if a word has more bits than the data width.
+    pub fn set_memory(&mut self, component_index: usize, words: &[usize]) -> Result<(), MemoryError> {
+        let component = self.circuit.component(component_index);
 
-                let owned_values = if component.function().output_value_count() != 0 {
-                    component.owned_value_indices().iter().map(|&value_index| self.owned_values[value_index]).collect()
-                } else {
-                    vec![]
-                };
+        let Some((word_count, data_width)) = component.function().memory_layout() else {
+            return Err(MemoryError::NotAMemory { component_index });
+        };
 
-                let (new_output_values, new_owned_values) = component.function().evaluate(&input_values, &owned_values);
+        if words.len() > word_count {
+            return Err(MemoryError::TooManyWords { count: words.len(), word_count });
+        }
+
+        memory::check_word_widths(words, data_width)?;
+
+        let owned_value_indices = component.owned_value_indices().to_vec();
+
+        self.record(|simulator| {
+            for (word_index, &word) in words.iter().enumerate() {
+                let value_indices = &owned_value_indices[word_index * data_width..(word_index + 1) * data_width];
 
-                for i in 0..component.owned_value_indices().len() {
-                    let value_index = component.owned_value_indices()[i];
-                    self.owned_values[value_index] = new_owned_values[i];
+                for (&value_index, value) in value_indices.iter().zip(to_values(word, data_width)) {
+                    simulator.set_owned_value(value_index, value);
                 }
+            }
+
+            simulator.evaluate_component(component_index);
+        });
 
-                let value_changes = old_output_values.iter().zip(new_output_values.iter())
-                    .enumerate()
-                    .filter(|(_, (before, after))| before != after).map(|(i, (_, after))| (i, after))
-                    .map(|(component_output_index, value)| (component.output_value_indices()[component_output_index], value));
+        Ok(())
+    }
 
-                value_changes.clone().for_each(|(output_index, &value)| self.values[output_index] = value);
+    /// Sets the power-on state of a component, which is used from the next
+    /// reset. See `Circuit::set_initial_state` for the errors.
+    pub fn set_initial_state(&mut self, component_index: usize, initial_state: Vec<Value>) -> Result<(), CircuitError> {
+        self.circuit.set_initial_state(component_index, initial_state)
+    }
 
-                for index in value_changes.map(|(output_index, _)| output_index) {
-                    if self.changed_values.contains(&index) {
-                        self.changed_values.push_back(index);
+    /// Replaces the table of a truth table component by one with the same
+    /// number of inputs and outputs. Returns false if that is not possible.
+    pub fn set_truth_table(&mut self, component_index: usize, table: TruthTable) -> bool {
+        let Function::TruthTable(old_table) = self.circuit.component(component_index).function() else {
+            return false;
+        };
+
+        if old_table.input_count() != table.input_count() || old_table.output_count() != table.output_count() {
+            return false;
+        }
+
+        *self.circuit.all_components_mut()[component_index].function_mut() = Function::TruthTable(table);
+        self.history.clear();
+        self.evaluate_component(component_index);
+        true
+    }
+
+    /// Value indices of the buses that are currently driven to different values.
+    pub fn bus_contentions(&self) -> &BTreeSet<usize> {
+        &self.bus_contentions
+    }
+
+    pub fn time(&self) -> usize {
+        self.time
+    }
+
+    pub fn config(&self) -> &SimulatorConfig {
+        &self.config
+    }
+
+    /// Number of steps that propagated a changed value so far.
+    pub fn step_count(&self) -> usize {
+        self.step_count
+    }
+
+    /// Events recorded as selected by `SimulatorConfig::trace`, oldest first.
+    pub fn trace(&self) -> &[TraceEvent] {
+        &self.trace
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.trace.clear();
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Adds a breakpoint and returns its index.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    /// Removes the breakpoint at `breakpoint_index`, which moves the following
+    /// breakpoints one index down.
+    pub fn remove_breakpoint(&mut self, breakpoint_index: usize) -> Option<Breakpoint> {
+        (breakpoint_index < self.breakpoints.len()).then(|| self.breakpoints.remove(breakpoint_index))
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// The event that changed the value most recently, if it changed since
+    /// the simulation started.
+    pub fn cause(&self, value_index: usize) -> Option<Cause> {
+        self.causes.get(&value_index).copied()
+    }
+
+    /// Follows the causes of a value back through the values that triggered
+    /// the components setting them, as far as those have not changed since.
+    pub fn explain(&self, value_index: usize) -> Explanation {
+        let cause = self.cause(value_index);
+
+        let trigger = match cause {
+            Some(Cause { step, source: CauseSource::Component { trigger: Some(trigger), .. } }) => {
+                let unchanged_since = self.cause(trigger).is_none_or(|trigger_cause| trigger_cause.step < step);
+                unchanged_since.then(|| Box::new(self.explain(trigger)))
+            },
+            _ => None,
+        };
+
+        Explanation { value_index, value: self.values[value_index], cause, trigger }
+    }
+
+    pub fn step(&mut self) {
+        if self.changed_values.is_empty() {
+            return;
+        }
+
+        self.record(|simulator| {
+            let position = match simulator.config.event_order {
+                EventOrder::Fifo => 0,
+                EventOrder::Lifo => simulator.changed_values.len() - 1,
+                EventOrder::Random { .. } => next_random(&mut simulator.random_state) as usize % simulator.changed_values.len(),
+            };
+            let value_to_check = simulator.changed_values.remove(position).unwrap();
+
+            if let Some(entry) = &mut simulator.recording {
+                entry.popped_value = Some((position, value_to_check));
+            }
+
+            simulator.step_count += 1;
+            simulator.evaluation_trigger = Some(value_to_check);
+            let components_to_update = simulator.find_components_by_input(value_to_check);
+
+            for component_index in components_to_update {
+                simulator.evaluate_component(component_index);
+            }
+
+            simulator.evaluation_trigger = None;
+        });
+    }
+
+    /// Undoes the last step, tick or input change. Returns false if the
+    /// history is empty.
+    pub fn step_back(&mut self) -> bool {
+        let Some(entry) = self.history.pop_back() else {
+            return false;
+        };
+
+        let new_len = self.changed_values.len() - entry.pushed_value_count;
+        self.changed_values.truncate(new_len);
+
+        if let Some((position, value_index)) = entry.popped_value {
+            self.changed_values.insert(position, value_index);
+        }
+
+        for &(value_index, value) in entry.old_values.iter().rev() {
+            self.values[value_index] = value;
+        }
+
+        for &(value_index, value) in entry.old_owned_values.iter().rev() {
+            self.owned_values[value_index] = value;
+        }
+
+        for &(value_index, cause) in entry.old_causes.iter().rev() {
+            match cause {
+                Some(cause) => self.causes.insert(value_index, cause),
+                None => self.causes.remove(&value_index),
+            };
+        }
+
+        for &(value_index, contention) in entry.old_bus_contentions.iter().rev() {
+            if contention {
+                self.bus_contentions.insert(value_index);
+            } else {
+                self.bus_contentions.remove(&value_index);
+            }
+        }
+
+        self.step_count = entry.step_count;
+        self.time = entry.time;
+        self.random_state = entry.random_state;
+
+        true
+    }
+
+    /// Goes back in the history until at most `step` steps are done, or steps
+    /// forward until `step` steps are done. Returns false if the history or
+    /// the pending changes end before that.
+    pub fn go_to_step(&mut self, step: usize) -> bool {
+        while self.step_count > step {
+            if !self.step_back() {
+                return false;
+            }
+        }
+
+        while self.step_count < step {
+            if self.changed_values.is_empty() {
+                return false;
+            }
+
+            self.step();
+        }
+
+        true
+    }
+
+    /// The earliest step that can be returned to with `go_to_step`.
+    pub fn oldest_step(&self) -> usize {
+        self.history.front().map_or(self.step_count, |entry| entry.step_count)
+    }
+
+    /// Reconstructs the values after `step` steps from the history, or
+    /// returns `None` if the step is not in the history.
+    pub fn values_at_step(&self, step: usize) -> Option<Vec<Value>> {
+        if step > self.step_count || step < self.oldest_step() {
+            return None;
+        }
+
+        let mut values = self.values.clone();
+
+        for entry in self.history.iter().rev().take_while(|entry| entry.steps_after() > step) {
+            for &(value_index, value) in entry.old_values.iter().rev() {
+                values[value_index] = value;
+            }
+        }
+
+        Some(values)
+    }
+
+    /// Lists the value indices whose values differ between two steps in the
+    /// history, with the values at `first_step` and `second_step`.
+    pub fn compare_steps(&self, first_step: usize, second_step: usize) -> Option<Vec<(usize, Value, Value)>> {
+        let first_values = self.values_at_step(first_step)?;
+        let second_values = self.values_at_step(second_step)?;
+
+        Some(first_values.into_iter().zip(second_values).enumerate()
+            .filter(|(_, (first, second))| first != second)
+            .map(|(value_index, (first, second))| (value_index, first, second))
+            .collect())
+    }
+
+    /// Advances the simulator time by one tick and updates all clocks.
+    /// The changes caused by the clocks still have to be simulated.
+    pub fn tick(&mut self) {
+        self.record(|simulator| {
+            simulator.time += 1;
+
+            for component_index in 0..simulator.circuit.all_components().len() {
+                if let Some(value) = simulator.circuit.component(component_index).function().clock_value(simulator.time) {
+                    let owned_value_index = simulator.circuit.component(component_index).owned_value_indices()[0];
+
+                    if simulator.owned_values[owned_value_index] != value {
+                        simulator.set_owned_value(owned_value_index, value);
+                        simulator.evaluate_component(component_index);
                     }
                 }
             }
-        }
+        });
     }
 
-    pub fn simulate(&mut self) -> bool {
+    /// Steps until no values change anymore. If that takes more steps than the
+    /// step limit, the simulation continues until its state repeats to report
+    /// the oscillation, so the simulator is left somewhere in the cycle.
+    pub fn simulate(&mut self) -> Result<(), Interruption> {
+        // input changes and ticks can hit a breakpoint before the first step
+        if let Some(hit) = self.breakpoint_hit.take() {
+            return Err(Interruption::Breakpoint(hit));
+        }
+
         let mut step_count: usize = 0;
 
         while !self.changed_values.is_empty() {
             step_count += 1;
 
-            if step_count > self.steps_until_unstable.into() {
-                return false;
+            if step_count > self.config.steps_until_unstable.into() {
+                return Err(Interruption::Oscillation(self.diagnose_oscillation()));
             }
 
             self.step();
+
+            if let Some(hit) = self.breakpoint_hit.take() {
+                return Err(Interruption::Breakpoint(hit));
+            }
         }
 
-        true
+        Ok(())
+    }
+
+    /// Simulates until stable, then advances the time tick by tick and simulates
+    /// after every tick. Returns the first oscillation if any simulation was
+    /// unstable, and stops right away when a breakpoint occurs.
+    pub fn simulate_ticks(&mut self, ticks: usize) -> Result<(), Interruption> {
+        let mut result = self.simulate();
+
+        for _ in 0..ticks {
+            if let Err(Interruption::Breakpoint(_)) = result {
+                break;
+            }
+
+            self.tick();
+            let tick_result = self.simulate();
+
+            if let Err(Interruption::Breakpoint(_)) = tick_result {
+                return tick_result;
+            }
+
+            result = result.and(tick_result);
+        }
+
+        result
+    }
+
+    /// Steps until the state of the simulator repeats and reports what changes
+    /// during one cycle.
+    fn diagnose_oscillation(&mut self) -> Oscillation {
+        let mut seen_states = HashMap::new();
+        let mut history = vec![self.values.clone()];
+        let mut period = None;
+
+        for step_count in 0..self.config.steps_until_unstable.get() {
+            if self.changed_values.is_empty() {
+                break;
+            }
+
+            let state = (self.values.clone(), self.owned_values.clone(), self.changed_values.clone());
+
+            if let Some(start) = seen_states.insert(state, step_count) {
+                period = Some(step_count - start);
+                history.drain(..start);
+                break;
+            }
+
+            self.step();
+            history.push(self.values.clone());
+        }
+
+        let toggling_values: BTreeSet<usize> = (0..self.values.len())
+            .filter(|&value_index| history.iter().any(|values| values[value_index] != history[0][value_index]))
+            .collect();
+
+        let feedback_components = self.circuit.all_components().iter()
+            .enumerate()
+            .filter(|(_, component)| component.input_value_indices().iter().any(|value_index| toggling_values.contains(value_index)))
+            .filter(|(_, component)| component.output_value_indices().iter().any(|value_index| toggling_values.contains(value_index)))
+            .map(|(component_index, _)| component_index)
+            .collect();
+
+        Oscillation { period, toggling_values, feedback_components }
+    }
+
+    fn initialize_source(&mut self, component_index: usize) {
+        let component = self.circuit.component(component_index);
+
+        if component.function().input_value_count() != 0 {
+            return;
+        }
+
+        if let Some(value) = component.function().clock_value(self.time) {
+            let owned_value_index = component.owned_value_indices()[0];
+            self.set_owned_value(owned_value_index, value);
+        }
+
+        self.evaluate_component(component_index);
+    }
+
+    fn evaluate_component(&mut self, component_index: usize) {
+        let component = self.circuit.component(component_index);
+        let input_values: Vec<Value> = component.input_value_indices().iter()
+            .map(|&value_index| self.values[value_index])
+            .collect();
+        let old_output_values: Vec<Value> = component.output_value_indices().iter()
+            .map(|&value_index| self.values[value_index])
+            .collect();
+
+        let owned_values = if component.function().output_value_count() != 0 {
+            component.owned_value_indices().iter().map(|&value_index| self.owned_values[value_index]).collect()
+        } else {
+            vec![]
+        };
+
+        let (mut new_output_values, new_owned_values) = component.function().evaluate(&input_values, &owned_values);
+        let owned_value_indices = component.owned_value_indices().to_vec();
+        let output_value_indices = component.output_value_indices().to_vec();
+
+        for (value_index, new_owned_value) in owned_value_indices.into_iter().zip(new_owned_values) {
+            if self.owned_values[value_index] != new_owned_value {
+                self.set_owned_value(value_index, new_owned_value);
+            }
+        }
+
+        for (new_output_value, &value_index) in new_output_values.iter_mut().zip(&output_value_indices) {
+            if self.circuit.is_bus(value_index) {
+                let (value, contention) = self.resolve_bus(value_index);
+                *new_output_value = value;
+                self.set_contention(value_index, contention);
+            }
+        }
+
+        let value_changes: Vec<(usize, Value)> = old_output_values.iter().zip(new_output_values)
+            .zip(output_value_indices)
+            .filter(|((&before, after), _)| before != *after)
+            .map(|((_, after), value_index)| (value_index, after))
+            .collect();
+
+        let cause = Cause { step: self.step_count, source: CauseSource::Component { component_index, trigger: self.evaluation_trigger } };
+
+        for &(value_index, value) in &value_changes {
+            self.set_value(value_index, value);
+            self.set_cause(value_index, Some(cause));
+        }
+
+        if self.config.trace.components {
+            self.trace.push(TraceEvent::ComponentEvaluated { step: self.step_count, component_index });
+        }
+
+        if !self.breakpoints.is_empty() {
+            let breakpoint_index = self.breakpoints.iter()
+                .position(|&breakpoint| breakpoint == Breakpoint::ComponentEvaluated { component_index });
+            self.hit_breakpoint(breakpoint_index);
+        }
+
+        if self.config.trace.values {
+            for &(value_index, value) in &value_changes {
+                self.trace.push(TraceEvent::ValueChanged { step: self.step_count, value_index, value });
+            }
+        }
+
+        for (value_index, _) in value_changes {
+            if !self.changed_values.contains(&value_index) {
+                self.queue_changed_value(value_index);
+            }
+        }
+    }
+
+    /// Resolves the bus again, for example after its configuration changed,
+    /// and queues its value if that changed.
+    fn refresh_bus(&mut self, bus_value_index: usize) {
+        let (value, contention) = self.resolve_bus(bus_value_index);
+        self.set_contention(bus_value_index, contention);
+
+        if self.values[bus_value_index] != value {
+            self.set_value(bus_value_index, value);
+            self.set_cause(bus_value_index, Some(Cause { step: self.step_count, source: CauseSource::Edit }));
+
+            if !self.changed_values.contains(&bus_value_index) {
+                self.queue_changed_value(bus_value_index);
+            }
+        }
+    }
+
+    /// Runs an operation and keeps what it overwrote in the history, unless
+    /// it is part of another recorded operation.
+    fn record(&mut self, operation: impl FnOnce(&mut Self)) {
+        if self.config.history_limit == 0 || self.recording.is_some() {
+            operation(self);
+            return;
+        }
+
+        self.recording = Some(HistoryEntry {
+            step_count: self.step_count,
+            time: self.time,
+            random_state: self.random_state,
+            ..HistoryEntry::default()
+        });
+
+        operation(self);
+
+        let entry = self.recording.take().unwrap();
+        if self.history.len() >= self.config.history_limit {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+
+    fn set_value(&mut self, value_index: usize, value: Value) {
+        if let Some(entry) = &mut self.recording {
+            entry.old_values.push((value_index, self.values[value_index]));
+        }
+
+        self.values[value_index] = value;
+
+        if self.breakpoints.is_empty() {
+            return;
+        }
+
+        let outputs = self.circuit.all_outputs();
+        let breakpoint_index = self.breakpoints.iter().position(|&breakpoint| match breakpoint {
+            Breakpoint::ValueBecomes { value_index: index, value: expected } => index == value_index && expected == value,
+            Breakpoint::OutputChanges { output_index } => outputs.get(output_index).is_some_and(|output| output.value_index() == value_index),
+            Breakpoint::ComponentEvaluated { .. } => false,
+        });
+        self.hit_breakpoint(breakpoint_index);
+    }
+
+    fn set_cause(&mut self, value_index: usize, cause: Option<Cause>) {
+        if self.skip_causes {
+            return;
+        }
+
+        let old_cause = match cause {
+            Some(cause) => self.causes.insert(value_index, cause),
+            None => self.causes.remove(&value_index),
+        };
+
+        if let Some(entry) = &mut self.recording {
+            entry.old_causes.push((value_index, old_cause));
+        }
+    }
+
+    /// Keeps the first breakpoint that occurs during a step.
+    fn hit_breakpoint(&mut self, breakpoint_index: Option<usize>) {
+        if let (None, Some(breakpoint_index)) = (self.breakpoint_hit, breakpoint_index) {
+            let breakpoint = self.breakpoints[breakpoint_index];
+            self.breakpoint_hit = Some(BreakpointHit { breakpoint_index, breakpoint, step: self.step_count });
+        }
+    }
+
+    fn set_owned_value(&mut self, value_index: usize, value: Value) {
+        if let Some(entry) = &mut self.recording {
+            entry.old_owned_values.push((value_index, self.owned_values[value_index]));
+        }
+
+        self.owned_values[value_index] = value;
+    }
+
+    fn set_contention(&mut self, bus_value_index: usize, contention: bool) {
+        if self.bus_contentions.contains(&bus_value_index) == contention {
+            return;
+        }
+
+        if let Some(entry) = &mut self.recording {
+            entry.old_bus_contentions.push((bus_value_index, !contention));
+        }
+
+        if contention {
+            self.bus_contentions.insert(bus_value_index);
+        } else {
+            self.bus_contentions.remove(&bus_value_index);
+        }
+    }
+
+    fn queue_changed_value(&mut self, value_index: usize) {
+        self.changed_values.push_back(value_index);
+
+        if let Some(entry) = &mut self.recording {
+            entry.pushed_value_count += 1;
+        }
+    }
+
+    /// Combines the values driven onto the bus by all its drivers as
+    /// configured for the bus and returns whether the drivers are in contention.
+    fn resolve_bus(&self, bus_value_index: usize) -> (Value, bool) {
+        let mut driven_values = Vec::new();
+
+        for component in self.circuit.all_components() {
+            let Some(output_position) = component.output_value_indices().iter().position(|&value_index| value_index == bus_value_index) else {
+                continue;
+            };
+
+            let owned_values: Vec<Value> = component.owned_value_indices().iter().map(|&value_index| self.owned_values[value_index]).collect();
+
+            if let Some(Some(value)) = component.function().driven_values(&owned_values).map(|driven| driven[output_position]) {
+                driven_values.push(value);
+            }
+        }
+
+        self.circuit.bus(bus_value_index).expect("value is not a bus").resolve(&driven_values)
     }
 
     fn find_components_by_input(&mut self, input_value_index: usize) -> Vec<usize> {
@@ -157,3 +1083,825 @@ impl Simulator {
             .collect()
     }
 }
+
+/// Advances the state of a splitmix64 generator and returns the next number.
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::CircuitMismatch => write!(f, "the snapshot does not belong to this circuit"),
+            SnapshotError::InvalidCircuit(error) => write!(f, "invalid circuit: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Display for Oscillation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.period {
+            Some(period) => write!(f, "oscillation with a period of {period} steps")?,
+            None => write!(f, "oscillation without a repeating state")?,
+        }
+
+        write!(f, " toggling values {:?} in a loop of components {:?}", self.toggling_values, self.feedback_components)
+    }
+}
+
+impl std::error::Error for Oscillation {}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::ValueBecomes { value_index, value } => write!(f, "value {value_index} becomes {value}"),
+            Breakpoint::ComponentEvaluated { component_index } => write!(f, "component {component_index} is evaluated"),
+            Breakpoint::OutputChanges { output_index } => write!(f, "output {output_index} changes"),
+        }
+    }
+}
+
+impl Display for BreakpointHit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "breakpoint {} ({}) at step {}", self.breakpoint_index, self.breakpoint, self.step)
+    }
+}
+
+impl Display for Interruption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interruption::Oscillation(oscillation) => write!(f, "{oscillation}"),
+            Interruption::Breakpoint(hit) => write!(f, "stopped at {hit}"),
+        }
+    }
+}
+
+impl std::error::Error for Interruption {}
+
+#[cfg(test)]
+mod tests {
+    use crate::function::ClockEdge;
+
+    use super::*;
+
+    #[test]
+    fn memory() {
+        let mut circuit = Circuit::new();
+        let address: Vec<_> = (0..2).map(|_| circuit.add_input().1).collect();
+        let data: Vec<_> = (0..4).map(|_| circuit.add_input().1).collect();
+        let (_, write_enable) = circuit.add_input();
+        let (_, clock) = circuit.add_input();
+        let (ram_index, output_indices) = circuit.add_component(Function::Ram { address_width: 2, data_width: 4, edge: ClockEdge::Rising }, [address, data, vec![write_enable, clock]].concat()).unwrap();
+        let (and_index, _) = circuit.add_component(Function::And, vec![0, 1]).unwrap();
+        output_indices.iter().for_each(|&value_index| { circuit.add_output(value_index); });
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.memory(ram_index), Some(vec![0; 4]));
+        assert_eq!(simulator.memory(and_index), None);
+
+        assert!(simulator.set_memory(ram_index, &[0xa, 0x5, 0xf]).is_ok());
+        assert!(matches!(simulator.set_memory(ram_index, &[0; 5]), Err(MemoryError::TooManyWords { count: 5, word_count: 4 })));
+        assert!(matches!(simulator.set_memory(ram_index, &[0x1, 0x10]), Err(MemoryError::WordTooWide { address: 1, word: 0x10, data_width: 4 })));
+        assert!(matches!(simulator.set_memory(and_index, &[0]), Err(MemoryError::NotAMemory { .. })));
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.memory(ram_index), Some(vec![0xa, 0x5, 0xf, 0x0]));
+
+        // address 0 is selected, so the outputs show the new word right away
+        let outputs: Vec<_> = (0..4).map(|output_index| simulator.get_output_value(output_index)).collect();
+        assert_eq!(to_number(&outputs), 0xa);
+
+        // write 0x3 to address 3 through the pins
+        [0, 1].iter().for_each(|&input_index| simulator.set_input(input_index, Value::On));
+        [2, 3].iter().for_each(|&input_index| simulator.set_input(input_index, Value::On));
+        simulator.set_input(6, Value::On);
+        simulator.set_input(7, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.memory(ram_index), Some(vec![0xa, 0x5, 0xf, 0x3]));
+
+        // the memory is part of a saved session
+        let serialized = serde_json::to_string(&simulator.session()).unwrap();
+        let loaded = Simulator::from_session(serde_json::from_str(&serialized).unwrap()).unwrap();
+        assert_eq!(loaded.memory(ram_index), Some(vec![0xa, 0x5, 0xf, 0x3]));
+    }
+
+    #[test]
+    fn bus_resolution() {
+        let mut circuit = Circuit::new();
+        let (_, data0) = circuit.add_input();
+        let (_, enable0) = circuit.add_input();
+        let (_, data1) = circuit.add_input();
+        let (_, enable1) = circuit.add_input();
+
+        let bus = circuit.add_bus(1, BusResolution::TriState, None);
+        circuit.add_bus_driver(Function::TriStateBuffer { width: 1 }, vec![data0, enable0], bus.clone()).unwrap();
+        circuit.add_bus_driver(Function::TriStateBuffer { width: 1 }, vec![data1, enable1], bus.clone()).unwrap();
+        let (_, not_index) = circuit.add_component(Function::Not, bus.clone()).unwrap();
+        circuit.add_output(bus[0]);
+        circuit.add_output(not_index[0]);
+
+        // only bus drivers with one bus per output can be added
+        assert_eq!(circuit.add_bus_driver(Function::Not, vec![data0], bus.clone()), Err(CircuitError::NotABusDriver));
+        assert_eq!(circuit.add_bus_driver(Function::TriStateBuffer { width: 2 }, vec![data0, data1, enable0], bus.clone()), Err(CircuitError::BusCount { expected: 2, actual: 1 }));
+        assert_eq!(circuit.add_bus_driver(Function::TriStateBuffer { width: 1 }, vec![data0, enable0], not_index.clone()), Err(CircuitError::NotABus(not_index[0])));
+        assert_eq!(circuit.set_bus_resolution(not_index[0], BusResolution::WiredOr, None), Err(CircuitError::NotABus(not_index[0])));
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+
+        // no driver is active
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert!(simulator.bus_contentions().is_empty());
+
+        // the first driver drives On
+        simulator.set_input(0, Value::On);
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+        assert_eq!(simulator.get_output_value(1), Value::Off);
+
+        // both drivers agree
+        simulator.set_input(2, Value::On);
+        simulator.set_input(3, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+        assert!(simulator.bus_contentions().is_empty());
+
+        // the drivers disagree, which is reported no matter which one changed last
+        simulator.set_input(2, Value::Off);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.bus_contentions().iter().copied().collect::<Vec<_>>(), bus);
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+
+        // only the second driver is left
+        simulator.set_input(1, Value::Off);
+        assert!(simulator.simulate().is_ok());
+        assert!(simulator.bus_contentions().is_empty());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert_eq!(simulator.get_output_value(1), Value::On);
+
+        simulator.set_input(2, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+    }
+
+    #[test]
+    fn wired_and_with_pull_up() {
+        let mut circuit = Circuit::new();
+        let (_, pull_low0) = circuit.add_input();
+        let (_, pull_low1) = circuit.add_input();
+        let (_, release0) = circuit.add_component(Function::Not, vec![pull_low0]).unwrap();
+        let (_, release1) = circuit.add_component(Function::Not, vec![pull_low1]).unwrap();
+
+        let (_, line) = circuit.add_component(Function::OpenDrain { width: 1 }, release0).unwrap();
+        circuit.add_bus_driver(Function::OpenDrain { width: 1 }, release1, line.clone()).unwrap();
+        circuit.add_output(line[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+
+        // released by both drivers, the pull-up keeps the line On
+        assert_eq!(simulator.get_output_value(0), Value::On);
+
+        simulator.set_input(0, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+
+        // pulling low with several drivers is no contention
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert!(simulator.bus_contentions().is_empty());
+
+        simulator.set_input(0, Value::Off);
+        simulator.set_input(1, Value::Off);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+
+        // without the pull-up a released line is Off
+        simulator.set_bus_resolution(line[0], BusResolution::WiredAnd, None).unwrap();
+        assert_eq!(simulator.cause(line[0]), Some(Cause { step: simulator.step_count(), source: CauseSource::Edit }));
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+    }
+
+    #[test]
+    fn wired_or() {
+        let mut circuit = Circuit::new();
+        let (_, data0) = circuit.add_input();
+        let (_, data1) = circuit.add_input();
+        let bus = circuit.add_bus(1, BusResolution::WiredOr, Some(Value::Off));
+        circuit.add_bus_driver(Function::OpenSource { width: 1 }, vec![data0], bus.clone()).unwrap();
+        circuit.add_bus_driver(Function::OpenSource { width: 1 }, vec![data1], bus.clone()).unwrap();
+        circuit.add_output(bus[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+
+        for (value0, value1) in [(Value::On, Value::Off), (Value::On, Value::On), (Value::Off, Value::On), (Value::Off, Value::Off)] {
+            simulator.set_input(0, value0);
+            simulator.set_input(1, value1);
+            assert!(simulator.simulate().is_ok());
+            assert_eq!(simulator.get_output_value(0), value0 | value1);
+            assert!(simulator.bus_contentions().is_empty());
+        }
+
+        // tri-state buffers on a wired-OR bus are combined the same way
+        let mut circuit = Circuit::new();
+        let (_, data0) = circuit.add_input();
+        let (_, data1) = circuit.add_input();
+        let (_, enable) = circuit.add_input();
+        let bus = circuit.add_bus(1, BusResolution::WiredOr, None);
+        circuit.add_bus_driver(Function::TriStateBuffer { width: 1 }, vec![data0, enable], bus.clone()).unwrap();
+        circuit.add_bus_driver(Function::TriStateBuffer { width: 1 }, vec![data1, enable], bus.clone()).unwrap();
+        circuit.add_output(bus[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        simulator.set_input(0, Value::On);
+        simulator.set_input(2, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+        assert!(simulator.bus_contentions().is_empty());
+    }
+
+    #[test]
+    fn latch_transparency() {
+        let mut circuit = Circuit::new();
+        let (_, data) = circuit.add_input();
+        let (_, enable) = circuit.add_input();
+        let (_, latch) = circuit.add_component(Function::LatchD, vec![data, enable]).unwrap();
+        let (_, inverted) = circuit.add_component(Function::Not, vec![latch[0]]).unwrap();
+        circuit.add_output(latch[0]);
+        circuit.add_output(inverted[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+
+        // changes of data pass through the open latch
+        for value in [Value::On, Value::Off, Value::On] {
+            simulator.set_input(0, value);
+            assert!(simulator.simulate().is_ok());
+            assert_eq!(simulator.get_output_value(0), value);
+            assert_eq!(simulator.get_output_value(1), !value);
+        }
+
+        simulator.set_input(1, Value::Off);
+        assert!(simulator.simulate().is_ok());
+
+        for value in [Value::Off, Value::On, Value::Off] {
+            simulator.set_input(0, value);
+            assert!(simulator.simulate().is_ok());
+            assert_eq!(simulator.get_output_value(0), Value::On);
+        }
+    }
+
+    #[test]
+    fn latch_hold_time() {
+        // the enable of the second latch arrives two gate delays late
+        let mut circuit = Circuit::new();
+        let (_, data) = circuit.add_input();
+        let (_, enable) = circuit.add_input();
+        let (_, inverted_enable) = circuit.add_component(Function::Not, vec![enable]).unwrap();
+        let (_, delayed_enable) = circuit.add_component(Function::Not, inverted_enable).unwrap();
+        let (_, latch) = circuit.add_component(Function::LatchD, vec![data, enable]).unwrap();
+        let (_, delayed_latch) = circuit.add_component(Function::LatchD, vec![data, delayed_enable[0]]).unwrap();
+        circuit.add_output(latch[0]);
+        circuit.add_output(delayed_latch[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert_eq!(simulator.get_output_value(1), Value::Off);
+
+        // closing the latches while data changes violates the hold time of the
+        // delayed latch, which still captures the new data
+        simulator.set_input(1, Value::Off);
+        simulator.set_input(0, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert_eq!(simulator.get_output_value(1), Value::On);
+    }
+
+    #[test]
+    fn gated_sr_latch() {
+        let mut circuit = Circuit::new();
+        let (_, set) = circuit.add_input();
+        let (_, reset) = circuit.add_input();
+        let (_, enable) = circuit.add_input();
+        let (_, latch) = circuit.add_component(Function::LatchSR, vec![set, reset, enable]).unwrap();
+        circuit.add_output(latch[0]);
+        circuit.add_output(latch[1]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        simulator.set_input(0, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+
+        // a set that is still active when enabled takes effect
+        simulator.set_input(2, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+        assert_eq!(simulator.get_output_value(1), Value::Off);
+
+        simulator.set_input(0, Value::Off);
+        simulator.set_input(2, Value::Off);
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+
+        simulator.set_input(2, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert_eq!(simulator.get_output_value(1), Value::On);
+    }
+
+    #[test]
+    fn oscillation_report() {
+        let mut circuit = Circuit::new();
+
+        // a ring of three inverters whose inputs refer to the next outputs
+        let (_, first) = circuit.add_component(Function::Not, vec![2]).unwrap();
+        let (_, second) = circuit.add_component(Function::Not, first.clone()).unwrap();
+        let (_, third) = circuit.add_component(Function::Not, second.clone()).unwrap();
+        assert_eq!(third, [2]);
+
+        // a stable part that only reads from the ring
+        let (_, input) = circuit.add_input();
+        let (_, and) = circuit.add_component(Function::And, vec![input, third[0]]).unwrap();
+        circuit.add_output(and[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        let Err(Interruption::Oscillation(oscillation)) = simulator.simulate() else {
+            panic!("the ring of inverters should oscillate");
+        };
+
+        assert!(oscillation.period.is_some_and(|period| period > 0));
+        assert_eq!(oscillation.toggling_values, BTreeSet::from([0, 1, 2]));
+        assert_eq!(oscillation.feedback_components, BTreeSet::from([0, 1, 2]));
+
+        let Err(Interruption::Oscillation(oscillation)) = simulator.simulate_ticks(2) else {
+            panic!("the ring of inverters should oscillate");
+        };
+        assert_eq!(oscillation.feedback_components, BTreeSet::from([0, 1, 2]));
+    }
+
+    #[test]
+    fn config() {
+        let mut circuit = Circuit::new();
+        let (_, input) = circuit.add_input();
+        let (_, inverted) = circuit.add_component(Function::Not, vec![input]).unwrap();
+        let (_, chain) = circuit.add_component(Function::Not, inverted.clone()).unwrap();
+        circuit.add_output(inverted[0]);
+        circuit.add_output(chain[0]);
+
+        // initial values
+        let with_initial_values = |initial_values| Simulator::with_config(circuit.clone(), SimulatorConfig { initial_values, ..SimulatorConfig::default() }).unwrap();
+        assert_eq!(with_initial_values(InitialValues::AllOn).values(), [Value::On; 3]);
+        assert_eq!(with_initial_values(InitialValues::Explicit(vec![Value::On])).values(), [Value::On, Value::Off, Value::Off]);
+        assert_eq!(with_initial_values(InitialValues::Random { seed: 7 }).values(), with_initial_values(InitialValues::Random { seed: 7 }).values());
+
+        let mut simulator = with_initial_values(InitialValues::AllOn);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert_eq!(simulator.get_output_value(1), Value::On);
+
+        // step limit
+        let config = SimulatorConfig { steps_until_unstable: NonZeroUsize::new(1).unwrap(), ..SimulatorConfig::default() };
+        assert!(Simulator::with_config(circuit.clone(), config).unwrap().simulate().is_err());
+
+        // every event order reaches the same stable state
+        for event_order in [EventOrder::Fifo, EventOrder::Lifo, EventOrder::Random { seed: 3 }] {
+            let mut simulator = Simulator::with_config(circuit.clone(), SimulatorConfig { event_order, ..SimulatorConfig::default() }).unwrap();
+            simulator.set_input(0, Value::On);
+            assert!(simulator.simulate().is_ok());
+            assert_eq!(simulator.values(), [Value::On, Value::Off, Value::On]);
+        }
+
+        // tracing
+        let trace = TraceOptions { values: true, components: true };
+        let mut simulator = Simulator::with_config(circuit.clone(), SimulatorConfig { trace, ..SimulatorConfig::default() }).unwrap();
+        assert!(simulator.simulate().is_ok());
+        simulator.clear_trace();
+
+        let step = simulator.step_count();
+        simulator.set_input(0, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.trace(), [
+            TraceEvent::ValueChanged { step, value_index: 0, value: Value::On },
+            TraceEvent::ComponentEvaluated { step: step + 1, component_index: 0 },
+            TraceEvent::ValueChanged { step: step + 1, value_index: 1, value: Value::Off },
+            TraceEvent::ComponentEvaluated { step: step + 2, component_index: 1 },
+            TraceEvent::ValueChanged { step: step + 2, value_index: 2, value: Value::On },
+        ]);
+    }
+
+    #[test]
+    fn snapshot_and_session() {
+        let mut circuit = Circuit::new();
+        let (_, data) = circuit.add_input();
+        let (_, clock) = circuit.add_input();
+        let (_, flip_flop) = circuit.add_component(Function::FlipFlopD { edge: ClockEdge::Rising }, vec![data, clock]).unwrap();
+        circuit.add_output(flip_flop[0]);
+
+        let mut simulator = Simulator::new(circuit.clone()).unwrap();
+        simulator.set_input(0, Value::On);
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+        simulator.set_input(0, Value::Off);
+        simulator.set_input(1, Value::Off);
+
+        // the snapshot keeps the stored bit and the pending changes
+        let snapshot = simulator.snapshot();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+
+        simulator.restore(&snapshot).unwrap();
+        assert_eq!(simulator.get_output_value(0), Value::On);
+        assert_eq!(simulator.snapshot(), snapshot);
+
+        // a session continues where it was saved
+        let serialized = serde_json::to_string(&simulator.session()).unwrap();
+        let mut loaded = Simulator::from_session(serde_json::from_str(&serialized).unwrap()).unwrap();
+        assert_eq!(loaded.snapshot(), snapshot);
+
+        loaded.set_input(0, Value::On);
+        simulator.set_input(0, Value::On);
+        assert!(loaded.simulate().is_ok());
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(loaded.snapshot(), simulator.snapshot());
+
+        let mut other = Simulator::new(Circuit::new()).unwrap();
+        assert_eq!(other.restore(&snapshot), Err(SnapshotError::CircuitMismatch));
+    }
+
+    #[test]
+    fn breakpoints() {
+        // a two bit counter of toggle flip-flops
+        let mut circuit = Circuit::new();
+        let (_, clock) = circuit.add_component(Function::Clock { period: NonZeroUsize::new(2).unwrap(), duty_cycle: 1 }, vec![]).unwrap();
+        let (_, on) = circuit.add_component(Function::On, vec![]).unwrap();
+        let (low_index, low) = circuit.add_component(Function::FlipFlopT { edge: ClockEdge::Rising }, vec![on[0], clock[0]]).unwrap();
+        let (_, inverted_low) = circuit.add_component(Function::Not, vec![low[0]]).unwrap();
+        let (_, high) = circuit.add_component(Function::FlipFlopT { edge: ClockEdge::Rising }, vec![on[0], inverted_low[0]]).unwrap();
+        circuit.add_output(low[0]);
+        circuit.add_output(high[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+
+        let high_on = simulator.add_breakpoint(Breakpoint::ValueBecomes { value_index: high[0], value: Value::On });
+        let Err(Interruption::Breakpoint(hit)) = simulator.simulate_ticks(20) else {
+            panic!("the high bit should become On");
+        };
+        assert_eq!(hit.breakpoint_index, high_on);
+        assert_eq!(hit.step, simulator.step_count());
+        assert_eq!(simulator.get_output_value(1), Value::On);
+
+        // the simulation continues after the breakpoint
+        assert!(simulator.remove_breakpoint(high_on).is_some());
+        assert_eq!(simulator.remove_breakpoint(high_on), None);
+        assert!(simulator.simulate().is_ok());
+
+        simulator.add_breakpoint(Breakpoint::OutputChanges { output_index: 0 });
+        simulator.add_breakpoint(Breakpoint::ComponentEvaluated { component_index: low_index });
+        simulator.tick();
+        let Err(Interruption::Breakpoint(hit)) = simulator.simulate() else {
+            panic!("the low bit should be evaluated");
+        };
+        assert_eq!(hit.breakpoint, Breakpoint::ComponentEvaluated { component_index: low_index });
+        assert_eq!(hit.to_string(), format!("breakpoint 1 (component {low_index} is evaluated) at step {}", hit.step));
+
+        simulator.clear_breakpoints();
+        assert!(simulator.simulate_ticks(4).is_ok());
+    }
+
+    #[test]
+    fn breakpoints_outside_of_steps() {
+        let mut circuit = Circuit::new();
+        let (_, input) = circuit.add_input();
+        let (clock_index, clock) = circuit.add_component(Function::Clock { period: NonZeroUsize::new(4).unwrap(), duty_cycle: 2 }, vec![]).unwrap();
+        circuit.add_output(clock[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+        let start_time = simulator.time();
+
+        // the clock is evaluated by the tick, outside of any step
+        let evaluated = simulator.add_breakpoint(Breakpoint::ComponentEvaluated { component_index: clock_index });
+        let Err(Interruption::Breakpoint(hit)) = simulator.simulate_ticks(8) else {
+            panic!("the clock should be evaluated");
+        };
+        assert_eq!(hit.breakpoint_index, evaluated);
+        assert_eq!(simulator.time(), start_time + 2);
+        simulator.clear_breakpoints();
+
+        let on = simulator.add_breakpoint(Breakpoint::ValueBecomes { value_index: clock[0], value: Value::On });
+        let Err(Interruption::Breakpoint(hit)) = simulator.simulate_ticks(8) else {
+            panic!("the clock output should turn On");
+        };
+        assert_eq!(hit.breakpoint_index, on);
+        assert_eq!(simulator.get_output_value(0), Value::On);
+        assert_eq!(simulator.time(), start_time + 4);
+        simulator.clear_breakpoints();
+
+        // input changes are checked as well
+        let input_on = simulator.add_breakpoint(Breakpoint::ValueBecomes { value_index: input, value: Value::On });
+        simulator.set_input(0, Value::On);
+        let Err(Interruption::Breakpoint(hit)) = simulator.simulate() else {
+            panic!("the input should turn On");
+        };
+        assert_eq!(hit.breakpoint_index, input_on);
+        assert!(simulator.simulate().is_ok());
+    }
+
+    #[test]
+    fn reset_and_initial_state() {
+        let mut circuit = Circuit::new();
+        let (_, data) = circuit.add_input();
+        let (_, clock) = circuit.add_input();
+        let (flip_flop_index, flip_flop) = circuit.add_component(Function::FlipFlopD { edge: ClockEdge::Rising }, vec![data, clock]).unwrap();
+        let (_, inverted) = circuit.add_component(Function::Not, vec![flip_flop[0]]).unwrap();
+        circuit.add_output(inverted[0]);
+        assert_eq!(circuit.set_initial_state(flip_flop_index, vec![Value::On]), Ok(()));
+        assert_eq!(circuit.set_initial_state(2, vec![]), Err(CircuitError::UnknownComponent(2)));
+        assert_eq!(circuit.set_initial_state(flip_flop_index, vec![Value::On; 3]), Err(CircuitError::InitialStateLength { owned: 2, actual: 3 }));
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        let initial = simulator.snapshot();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.value_for_index(flip_flop[0]), Value::On);
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        let stable = simulator.snapshot();
+
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.value_for_index(flip_flop[0]), Value::Off);
+        simulator.add_breakpoint(Breakpoint::OutputChanges { output_index: 0 });
+
+        // a reset replays the initial queue to the same stable state
+        simulator.reset();
+        assert_eq!(simulator.snapshot(), initial);
+        assert_eq!(simulator.breakpoints().len(), 1);
+        simulator.clear_breakpoints();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.snapshot(), stable);
+
+        assert_eq!(simulator.set_initial_state(flip_flop_index, vec![]), Ok(()));
+        assert_eq!(simulator.set_initial_state(flip_flop_index, vec![Value::On; 3]), Err(CircuitError::InitialStateLength { owned: 2, actual: 3 }));
+        assert_eq!(simulator.set_initial_state(5, vec![]), Err(CircuitError::UnknownComponent(5)));
+        simulator.reset();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+
+        assert_eq!(simulator.set_initial_state(flip_flop_index, vec![Value::On]), Ok(()));
+        let serialized = serde_json::to_string(simulator.circuit()).unwrap();
+        let circuit: Circuit = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(circuit.component(flip_flop_index).initial_state(), [Value::On]);
+
+        let mut circuit = Circuit::new();
+        let (_, address) = circuit.add_input();
+        let (_, write_enable) = circuit.add_input();
+        let (ram_index, _) = circuit.add_component(Function::Ram { address_width: 1, data_width: 2, edge: ClockEdge::Rising }, vec![address, address, address, write_enable, write_enable]).unwrap();
+        let (table_index, table_outputs) = circuit.add_component(Function::TruthTable(TruthTable::from_bits(1, 1, "01").unwrap()), vec![address]).unwrap();
+        circuit.add_output(table_outputs[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+        assert!(simulator.set_memory(ram_index, &[1, 2]).is_ok());
+        assert!(simulator.set_truth_table(table_index, TruthTable::from_bits(1, 1, "10").unwrap()));
+
+        // memory contents are state and cleared, while the edited table is part of the circuit
+        simulator.reset();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.memory(ram_index), Some(vec![0, 0]));
+        assert_eq!(simulator.get_output_value(0), Value::On);
+
+        // memory contents in the initial state survive a reset
+        assert_eq!(simulator.set_initial_state(ram_index, [to_values(1, 2), to_values(2, 2)].concat()), Ok(()));
+        simulator.reset();
+        assert_eq!(simulator.memory(ram_index), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn causality() {
+        let mut circuit = Circuit::new();
+        let (_, a) = circuit.add_input();
+        let (_, b) = circuit.add_input();
+        let (not_index, not) = circuit.add_component(Function::Not, vec![a]).unwrap();
+        let (and_index, and) = circuit.add_component(Function::And, vec![not[0], b]).unwrap();
+        circuit.add_output(and[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.cause(and[0]), None);
+
+        simulator.set_input(1, Value::On);
+        let input_step = simulator.step_count();
+        assert!(simulator.simulate().is_ok());
+
+        let explanation = simulator.explain(and[0]);
+        assert_eq!(explanation.value, Value::On);
+        let cause = explanation.cause.unwrap();
+        assert_eq!(cause.source, CauseSource::Component { component_index: and_index, trigger: Some(b) });
+
+        let trigger = explanation.trigger.unwrap();
+        assert_eq!(trigger.value_index, b);
+        assert_eq!(trigger.cause, Some(Cause { step: input_step, source: CauseSource::Input { input_index: 1 } }));
+        assert!(trigger.cause.unwrap().step < cause.step);
+        assert_eq!(trigger.trigger, None);
+
+        // the inverter output is followed back to the input that flipped it
+        simulator.set_input(0, Value::On);
+        assert!(simulator.simulate().is_ok());
+        let explanation = simulator.explain(and[0]);
+        assert_eq!(explanation.value, Value::Off);
+        assert_eq!(explanation.cause.unwrap().source, CauseSource::Component { component_index: and_index, trigger: Some(not[0]) });
+
+        let not_explanation = explanation.trigger.unwrap();
+        assert_eq!(not_explanation.cause.unwrap().source, CauseSource::Component { component_index: not_index, trigger: Some(a) });
+        assert_eq!(not_explanation.trigger.unwrap().cause.unwrap().source, CauseSource::Input { input_index: 0 });
+
+        // a trigger that changed again is not followed
+        for (input_index, value) in [(0, Value::Off), (1, Value::Off), (0, Value::On), (1, Value::On)] {
+            simulator.set_input(input_index, value);
+            assert!(simulator.simulate().is_ok());
+        }
+        let explanation = simulator.explain(and[0]);
+        assert_eq!(explanation.value, Value::Off);
+        assert_eq!(explanation.cause.unwrap().source, CauseSource::Component { component_index: and_index, trigger: Some(b) });
+        assert_eq!(explanation.trigger, None);
+
+        // stepping back restores the causes
+        let before = simulator.explain(not[0]);
+        simulator.set_input(0, Value::Off);
+        assert!(simulator.simulate().is_ok());
+        assert_ne!(simulator.explain(not[0]), before);
+        while simulator.step_back() && simulator.value_for_index(a) == Value::Off {}
+        assert_eq!(simulator.explain(not[0]), before);
+
+        // values set while the simulator is created are part of the start
+        let mut circuit = Circuit::new();
+        let (_, clock) = circuit.add_component(Function::Clock { period: NonZeroUsize::new(2).unwrap(), duty_cycle: 1 }, vec![]).unwrap();
+        circuit.add_output(clock[0]);
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert_eq!(simulator.get_output_value(0), Value::On);
+        assert_eq!(simulator.cause(clock[0]), None);
+        simulator.reset();
+        assert_eq!(simulator.cause(clock[0]), None);
+    }
+
+    #[test]
+    fn evaluation_only() {
+        let mut circuit = Circuit::new();
+        let (_, a) = circuit.add_input();
+        let (_, not) = circuit.add_component(Function::Not, vec![a]).unwrap();
+        circuit.add_output(not[0]);
+
+        let mut simulator = Simulator::for_evaluation(circuit);
+        simulator.set_input(0, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert_eq!(simulator.cause(not[0]), None);
+        assert!(!simulator.step_back());
+    }
+
+    #[test]
+    fn step_history() {
+        let mut circuit = Circuit::new();
+        let (_, data) = circuit.add_input();
+        let (_, clock) = circuit.add_input();
+        let (_, flip_flop) = circuit.add_component(Function::FlipFlopD { edge: ClockEdge::Rising }, vec![data, clock]).unwrap();
+        let (_, inverted) = circuit.add_component(Function::Not, vec![flip_flop[0]]).unwrap();
+        circuit.add_output(inverted[0]);
+
+        let config = SimulatorConfig { event_order: EventOrder::Random { seed: 3 }, ..SimulatorConfig::default() };
+        let mut simulator = Simulator::with_config(circuit, config).unwrap();
+        assert!(simulator.simulate().is_ok());
+        let start = simulator.snapshot();
+        let start_step = simulator.step_count();
+
+        simulator.set_input(0, Value::On);
+        simulator.set_input(1, Value::On);
+        let before_simulation = simulator.snapshot();
+        assert!(simulator.simulate().is_ok());
+        let end = simulator.snapshot();
+        let end_step = simulator.step_count();
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+
+        // undoing restores everything including the queue and random state
+        assert!(simulator.go_to_step(start_step + 1));
+        assert!(simulator.step_back());
+        assert_eq!(simulator.snapshot(), before_simulation);
+        assert!(simulator.go_to_step(end_step));
+        assert_eq!(simulator.snapshot(), end);
+        assert!(simulator.go_to_step(start_step));
+        assert!(simulator.step_back() && simulator.step_back());
+        assert_eq!(simulator.snapshot(), start);
+
+        // input changes are not replayed when stepping forward
+        assert!(!simulator.go_to_step(end_step));
+        simulator.set_input(0, Value::On);
+        simulator.set_input(1, Value::On);
+        assert!(simulator.go_to_step(end_step));
+        assert_eq!(simulator.snapshot(), end);
+        assert_eq!(simulator.values_at_step(end_step).unwrap(), simulator.values());
+        assert_eq!(simulator.compare_steps(start_step, start_step), Some(vec![]));
+
+        let inverted_change = (inverted[0], Value::On, Value::Off);
+        assert!(simulator.compare_steps(start_step, end_step).unwrap().contains(&inverted_change));
+        assert_eq!(simulator.values_at_step(end_step + 1), None);
+
+        // the history is bounded
+        let config = SimulatorConfig { history_limit: 2, ..SimulatorConfig::default() };
+        let mut simulator = Simulator::with_config(simulator.circuit().clone(), config).unwrap();
+        assert!(simulator.simulate().is_ok());
+        simulator.set_input(0, Value::On);
+        simulator.set_input(1, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert!(simulator.step_back() && simulator.step_back());
+        assert!(!simulator.step_back());
+        assert_eq!(simulator.oldest_step(), simulator.step_count());
+    }
+
+    #[test]
+    fn changes_propagate_through_chains() {
+        let mut circuit = Circuit::new();
+        let (_, input) = circuit.add_input();
+        let (_, first) = circuit.add_component(Function::Not, vec![input]).unwrap();
+        let (_, second) = circuit.add_component(Function::Not, vec![first[0]]).unwrap();
+        circuit.add_output(second[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+
+        // once the initial queue is processed, new changes still have to pass both inverters
+        simulator.set_input(0, Value::On);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+
+        simulator.set_input(0, Value::Off);
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+    }
+
+    #[test]
+    fn constant_sources() {
+        let mut circuit = Circuit::new();
+        let (_, on_index) = circuit.add_component(Function::On, vec![]).unwrap();
+        let (_, off_index) = circuit.add_component(Function::Off, vec![]).unwrap();
+        let (_, input_index) = circuit.add_input();
+        let (_, and_on_index) = circuit.add_component(Function::And, vec![on_index[0], input_index]).unwrap();
+        let (_, and_off_index) = circuit.add_component(Function::And, vec![off_index[0], input_index]).unwrap();
+        circuit.add_output(and_on_index[0]);
+        circuit.add_output(and_off_index[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        simulator.set_input(0, Value::On);
+        assert!(simulator.simulate().is_ok());
+
+        assert_eq!(simulator.get_output_value(0), Value::On);
+        assert_eq!(simulator.get_output_value(1), Value::Off);
+    }
+
+    #[test]
+    fn clock_drives_flip_flop() {
+        let mut circuit = Circuit::new();
+        let (_, clock_index) = circuit.add_component(Function::Clock { period: NonZeroUsize::new(2).unwrap(), duty_cycle: 1 }, vec![]).unwrap();
+        let (_, on_index) = circuit.add_component(Function::On, vec![]).unwrap();
+        let (_, t_index) = circuit.add_component(Function::FlipFlopT { edge: ClockEdge::Rising }, vec![on_index[0], clock_index[0]]).unwrap();
+        circuit.add_output(clock_index[0]);
+        circuit.add_output(t_index[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::On);
+
+        let mut clock_values = vec![];
+        let mut flip_flop_values = vec![];
+
+        for _ in 0..4 {
+            assert!(simulator.simulate_ticks(1).is_ok());
+            clock_values.push(simulator.get_output_value(0));
+            flip_flop_values.push(simulator.get_output_value(1));
+        }
+
+        assert_eq!(simulator.time(), 4);
+        assert_eq!(clock_values, [Value::Off, Value::On, Value::Off, Value::On]);
+        // the flip flop toggles on the rising edges at time 0, 2 and 4
+        assert_eq!(flip_flop_values, [Value::On, Value::Off, Value::Off, Value::On]);
+    }
+}
@@ -0,0 +1,637 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Value, Circuit, function::{ClockEdge, Function}};
+
+/// Moore or Mealy machine described by a state table, used by
+/// `Function::StateMachine` and synthesized into gates by `synthesize`.
+///
+/// The first state is the initial state. A state takes the first of its
+/// transitions whose condition matches the inputs on the clock edge given by
+/// `edge`, the rising one by default, and stays if none matches. Conditions are written like the inputs of a
+/// `TruthTable` row, e.g. `"1-0"` with input 0 first and `-` matching both
+/// values, where an empty condition always matches. Outputs are bit strings
+/// with output 0 first and empty outputs are all `Off`. A Moore machine takes
+/// its outputs from the current state, a Mealy machine from the transition
+/// that matches the current state and inputs, or `Off` if there is none.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "StateMachineData", into = "StateMachineData")]
+pub struct StateMachine {
+    kind: MachineKind,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    states: Vec<State>,
+    transitions: Vec<Transition>,
+    edge: ClockEdge,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MachineKind {
+    Moore,
+    Mealy,
+}
+
+/// How the states of a synthesized state machine are stored in flip-flops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateEncoding {
+    /// The index of the state as binary number.
+    Binary,
+    /// One flip-flop per state, where the flip-flop of the initial state is
+    /// inverted so that the machine starts in the initial state.
+    OneHot,
+    /// The index of the state as Gray code.
+    Gray,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct State {
+    name: String,
+    outputs: Vec<Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Transition {
+    from: usize,
+    condition: Vec<Option<Value>>,
+    to: usize,
+    outputs: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateMachineData {
+    kind: MachineKind,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    states: Vec<StateData>,
+    #[serde(default)]
+    transitions: Vec<TransitionData>,
+    #[serde(default)]
+    edge: ClockEdge,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateData {
+    name: String,
+    #[serde(default)]
+    outputs: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TransitionData {
+    from: String,
+    #[serde(default)]
+    condition: String,
+    to: String,
+    #[serde(default)]
+    outputs: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StateMachineError {
+    NoStates,
+    DuplicateState(String),
+    UnknownState(String),
+    InvalidBits { bits: String, expected: usize },
+}
+
+impl StateMachine {
+    pub fn new(kind: MachineKind, inputs: Vec<String>, outputs: Vec<String>) -> Self {
+        StateMachine { kind, inputs, outputs, states: vec![], transitions: vec![], edge: ClockEdge::Rising }
+    }
+
+    /// Adds a state with the outputs of a Moore machine, which are ignored by
+    /// Mealy machines. Returns the index of the state.
+    pub fn add_state(&mut self, name: &str, outputs: &str) -> Result<usize, StateMachineError> {
+        if self.state_index(name).is_some() {
+            return Err(StateMachineError::DuplicateState(name.to_owned()));
+        }
+
+        let outputs = parse_bits(outputs, self.outputs.len())?;
+        self.states.push(State { name: name.to_owned(), outputs });
+
+        Ok(self.states.len() - 1)
+    }
+
+    /// Adds a transition with the outputs of a Mealy machine, which are ignored
+    /// by Moore machines. It is taken after all earlier transitions of its state.
+    pub fn add_transition(&mut self, from: &str, condition: &str, to: &str, outputs: &str) -> Result<(), StateMachineError> {
+        let from = self.state_index(from).ok_or_else(|| StateMachineError::UnknownState(from.to_owned()))?;
+        let to = self.state_index(to).ok_or_else(|| StateMachineError::UnknownState(to.to_owned()))?;
+        let condition = parse_condition(condition, self.inputs.len())?;
+        let outputs = parse_bits(outputs, self.outputs.len())?;
+
+        self.transitions.push(Transition { from, condition, to, outputs });
+        Ok(())
+    }
+
+    pub fn set_edge(&mut self, edge: ClockEdge) {
+        self.edge = edge;
+    }
+
+    pub fn kind(&self) -> MachineKind {
+        self.kind
+    }
+
+    pub fn edge(&self) -> ClockEdge {
+        self.edge
+    }
+
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn state_name(&self, state: usize) -> &str {
+        &self.states[state].name
+    }
+
+    pub fn state_index(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|state| state.name == name)
+    }
+
+    /// Number of bits needed to store the index of a state.
+    pub fn state_bit_count(&self) -> usize {
+        StateEncoding::Binary.width(self.states.len())
+    }
+
+    /// The state the machine is in after a clock edge with the given inputs.
+    pub fn next_state(&self, state: usize, input_values: &[Value]) -> usize {
+        self.matching_transition(state, input_values).map_or(state, |transition| transition.to)
+    }
+
+    pub fn output_values(&self, state: usize, input_values: &[Value]) -> Vec<Value> {
+        match self.kind {
+            MachineKind::Moore => self.states[state].outputs.clone(),
+            MachineKind::Mealy => match self.matching_transition(state, input_values) {
+                Some(transition) => transition.outputs.clone(),
+                None => vec![Value::Off; self.outputs.len()],
+            },
+        }
+    }
+
+    fn matching_transition(&self, state: usize, input_values: &[Value]) -> Option<&Transition> {
+        self.transitions.iter()
+            .filter(|transition| transition.from == state)
+            .find(|transition| transition.condition.iter().zip(input_values).all(|(expected, &value)| expected.is_none_or(|expected| expected == value)))
+    }
+
+    /// Builds a circuit of `FlipFlopD`s and gates that behaves like the state
+    /// machine. Its inputs are the inputs of the machine followed by the clock.
+    pub fn synthesize(&self, encoding: StateEncoding) -> Circuit {
+        let mut circuit = Circuit::new();
+        let inputs: Vec<usize> = self.inputs.iter().map(|_| circuit.add_input().1).collect();
+        let (_, clock) = circuit.add_input();
+
+        // the data inputs are connected once the next state logic exists
+        let width = encoding.width(self.states.len());
+        let flip_flops: Vec<(usize, Vec<usize>)> = (0..width)
+            .map(|_| circuit.add_component_unchecked(Function::FlipFlopD { edge: self.edge }, vec![clock, clock]))
+            .collect();
+
+        let state_detectors: Vec<usize> = (0..self.states.len())
+            .map(|state| match encoding {
+                StateEncoding::OneHot if state == 0 => flip_flops[0].1[1],
+                StateEncoding::OneHot => flip_flops[state].1[0],
+                _ => {
+                    let code = encoding.code(state);
+                    let literals = flip_flops.iter().enumerate()
+                        .map(|(bit, (_, outputs))| if code >> bit & 1 == 1 { outputs[0] } else { outputs[1] })
+                        .collect();
+                    and(&mut circuit, literals)
+                },
+            })
+            .collect();
+
+        let mut inverted_inputs = vec![None; inputs.len()];
+        let conditions: Vec<usize> = self.transitions.iter()
+            .map(|transition| {
+                let literals = transition.condition.iter().enumerate()
+                    .filter_map(|(input, expected)| expected.map(|expected| match expected {
+                        Value::On => inputs[input],
+                        Value::Off => *inverted_inputs[input].get_or_insert_with(|| circuit.add_component_unchecked(Function::Not, vec![inputs[input]]).1[0]),
+                    }))
+                    .collect();
+                and(&mut circuit, literals)
+            })
+            .collect();
+        let inverted_conditions: Vec<usize> = conditions.iter()
+            .map(|&condition| circuit.add_component_unchecked(Function::Not, vec![condition]).1[0])
+            .collect();
+
+        // every transition and staying in a state where none matches is a term
+        // of the next state and output logic, which lists the target state and
+        // the transition
+        let mut terms: Vec<(usize, Option<usize>, usize)> = vec![];
+        for (state, &state_detector) in state_detectors.iter().enumerate() {
+            let mut earlier = vec![state_detector];
+
+            for (transition_index, transition) in self.transitions.iter().enumerate().filter(|(_, transition)| transition.from == state) {
+                let mut literals = earlier.clone();
+                literals.push(conditions[transition_index]);
+                terms.push((transition.to, Some(transition_index), and(&mut circuit, literals)));
+                earlier.push(inverted_conditions[transition_index]);
+            }
+
+            terms.push((state, None, and(&mut circuit, earlier)));
+        }
+
+        for (bit, (flip_flop_index, _)) in flip_flops.iter().enumerate() {
+            let data = match encoding {
+                StateEncoding::OneHot if bit == 0 => {
+                    let entering = terms.iter().filter(|(to, _, _)| *to == 0).map(|&(_, _, term)| term).collect();
+                    let entering = or(&mut circuit, entering);
+                    circuit.add_component_unchecked(Function::Not, vec![entering]).1[0]
+                },
+                StateEncoding::OneHot => {
+                    let entering = terms.iter().filter(|(to, _, _)| *to == bit).map(|&(_, _, term)| term).collect();
+                    or(&mut circuit, entering)
+                },
+                _ => {
+                    let setting = terms.iter().filter(|(to, _, _)| encoding.code(*to) >> bit & 1 == 1).map(|&(_, _, term)| term).collect();
+                    or(&mut circuit, setting)
+                },
+            };
+
+            circuit.set_component_input(*flip_flop_index, 0, data);
+        }
+
+        for output in 0..self.outputs.len() {
+            let active = match self.kind {
+                MachineKind::Moore => (0..self.states.len())
+                    .filter(|&state| self.states[state].outputs[output] == Value::On)
+                    .map(|state| state_detectors[state])
+                    .collect(),
+                MachineKind::Mealy => terms.iter()
+                    .filter(|(_, transition, _)| transition.is_some_and(|transition| self.transitions[transition].outputs[output] == Value::On))
+                    .map(|&(_, _, term)| term)
+                    .collect(),
+            };
+
+            let value_index = or(&mut circuit, active);
+            circuit.add_output(value_index);
+        }
+
+        circuit
+    }
+}
+
+impl StateEncoding {
+    /// Number of flip-flops needed for `state_count` states.
+    fn width(&self, state_count: usize) -> usize {
+        match self {
+            StateEncoding::OneHot => state_count.max(1),
+            _ => (usize::BITS - state_count.saturating_sub(1).leading_zeros()).max(1) as usize,
+        }
+    }
+
+    fn code(&self, state: usize) -> usize {
+        match self {
+            StateEncoding::Binary => state,
+            StateEncoding::OneHot => 1 << state,
+            StateEncoding::Gray => state ^ (state >> 1),
+        }
+    }
+}
+
+fn and(circuit: &mut Circuit, value_indices: Vec<usize>) -> usize {
+    match value_indices[..] {
+        [] => circuit.add_component_unchecked(Function::On, vec![]).1[0],
+        [value_index] => value_index,
+        _ => circuit.add_component_unchecked(Function::And, value_indices).1[0],
+    }
+}
+
+fn or(circuit: &mut Circuit, value_indices: Vec<usize>) -> usize {
+    match value_indices[..] {
+        [] => circuit.add_component_unchecked(Function::Off, vec![]).1[0],
+        [value_index] => value_index,
+        _ => circuit.add_component_unchecked(Function::Or, value_indices).1[0],
+    }
+}
+
+fn parse_bits(bits: &str, expected: usize) -> Result<Vec<Value>, StateMachineError> {
+    if bits.is_empty() {
+        return Ok(vec![Value::Off; expected]);
+    }
+
+    parse_condition(bits, expected)?.into_iter()
+        .map(|value| value.ok_or_else(|| StateMachineError::InvalidBits { bits: bits.to_owned(), expected }))
+        .collect()
+}
+
+fn parse_condition(condition: &str, expected: usize) -> Result<Vec<Option<Value>>, StateMachineError> {
+    if condition.is_empty() {
+        return Ok(vec![None; expected]);
+    }
+
+    let invalid = || StateMachineError::InvalidBits { bits: condition.to_owned(), expected };
+
+    if condition.chars().count() != expected {
+        return Err(invalid());
+    }
+
+    condition.chars()
+        .map(|ch| match ch {
+            '0' => Ok(Some(Value::Off)),
+            '1' => Ok(Some(Value::On)),
+            '-' => Ok(None),
+            _ => Err(invalid()),
+        })
+        .collect()
+}
+
+fn format_bits(values: &[Option<Value>]) -> String {
+    values.iter()
+        .map(|value| match value {
+            Some(Value::On) => '1',
+            Some(Value::Off) => '0',
+            None => '-',
+        })
+        .collect()
+}
+
+impl TryFrom<StateMachineData> for StateMachine {
+    type Error = StateMachineError;
+
+    fn try_from(data: StateMachineData) -> Result<Self, Self::Error> {
+        if data.states.is_empty() {
+            return Err(StateMachineError::NoStates);
+        }
+
+        let mut machine = StateMachine::new(data.kind, data.inputs, data.outputs);
+        machine.set_edge(data.edge);
+
+        for state in &data.states {
+            machine.add_state(&state.name, &state.outputs)?;
+        }
+
+        for transition in &data.transitions {
+            machine.add_transition(&transition.from, &transition.condition, &transition.to, &transition.outputs)?;
+        }
+
+        Ok(machine)
+    }
+}
+
+impl From<StateMachine> for StateMachineData {
+    fn from(machine: StateMachine) -> Self {
+        let output_bits = |values: &[Value]| format_bits(&values.iter().copied().map(Some).collect::<Vec<_>>());
+
+        StateMachineData {
+            kind: machine.kind,
+            states: machine.states.iter()
+                .map(|state| StateData { name: state.name.clone(), outputs: output_bits(&state.outputs) })
+                .collect(),
+            transitions: machine.transitions.iter()
+                .map(|transition| TransitionData {
+                    from: machine.states[transition.from].name.clone(),
+                    condition: format_bits(&transition.condition),
+                    to: machine.states[transition.to].name.clone(),
+                    outputs: output_bits(&transition.outputs),
+                })
+                .collect(),
+            inputs: machine.inputs,
+            outputs: machine.outputs,
+            edge: machine.edge,
+        }
+    }
+}
+
+impl Display for MachineKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Display for StateEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl Display for StateMachineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateMachineError::NoStates => write!(f, "a state machine needs at least one state"),
+            StateMachineError::DuplicateState(name) => write!(f, "state {name} is defined twice"),
+            StateMachineError::UnknownState(name) => write!(f, "state {name} is not defined"),
+            StateMachineError::InvalidBits { bits, expected } => write!(f, "\"{bits}\" is not a list of {expected} bits"),
+        }
+    }
+}
+
+impl std::error::Error for StateMachineError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CircuitError, simulator::Simulator, function::{FunctionError, to_values}};
+
+    use super::*;
+
+    /// Detects the input sequence 1, 1, 0 and outputs `On` in the state after it.
+    fn sequence_detector() -> StateMachine {
+        let mut machine = StateMachine::new(MachineKind::Moore, vec!["in".into()], vec!["found".into()]);
+        for (name, outputs) in [("Idle", "0"), ("One", "0"), ("OneOne", "0"), ("Found", "1")] {
+            machine.add_state(name, outputs).unwrap();
+        }
+
+        for (from, condition, to) in [("Idle", "1", "One"), ("One", "1", "OneOne"), ("One", "0", "Idle"), ("OneOne", "0", "Found"), ("Found", "1", "One"), ("Found", "0", "Idle")] {
+            machine.add_transition(from, condition, to, "").unwrap();
+        }
+
+        machine
+    }
+
+    /// Grants one of two requests, preferring request 0, until it is withdrawn.
+    /// It has three states so that the binary encoding has an unused code.
+    fn arbiter() -> StateMachine {
+        let mut machine = StateMachine::new(MachineKind::Mealy, vec!["request0".into(), "request1".into()], vec!["grant0".into(), "grant1".into()]);
+        for name in ["Ready", "Busy0", "Busy1"] {
+            machine.add_state(name, "").unwrap();
+        }
+
+        machine.add_transition("Ready", "1-", "Busy0", "10").unwrap();
+        machine.add_transition("Ready", "-1", "Busy1", "01").unwrap();
+        machine.add_transition("Busy0", "0-", "Ready", "").unwrap();
+        machine.add_transition("Busy1", "-0", "Ready", "").unwrap();
+        machine.add_transition("Busy1", "", "Busy1", "01").unwrap();
+
+        machine
+    }
+
+    /// Applies the inputs followed by a clock pulse and records the outputs
+    /// before and after the rising edge.
+    fn run(circuit: Circuit, input_sequence: &[Vec<Value>]) -> Vec<Vec<Value>> {
+        let output_count = circuit.all_outputs().len();
+        let clock_input = circuit.all_inputs().len() - 1;
+        let mut simulator = Simulator::new(circuit).unwrap();
+        let mut outputs = vec![];
+
+        for input_values in input_sequence {
+            for (input_index, &value) in input_values.iter().enumerate() {
+                simulator.set_input(input_index, value);
+            }
+
+            // the inputs settle before the falling edge reaches the flip-flops
+            assert!(simulator.simulate().is_ok());
+
+            for clock in [Value::Off, Value::On] {
+                simulator.set_input(clock_input, clock);
+                assert!(simulator.simulate().is_ok());
+                outputs.push((0..output_count).map(|output_index| simulator.get_output_value(output_index)).collect());
+            }
+        }
+
+        outputs
+    }
+
+    fn component_circuit(machine: &StateMachine) -> Circuit {
+        let mut circuit = Circuit::new();
+        let inputs = (0..=machine.inputs().len()).map(|_| circuit.add_input().1).collect();
+        let (_, outputs) = circuit.add_component(Function::StateMachine(machine.clone()), inputs).unwrap();
+        outputs.into_iter().for_each(|value_index| { circuit.add_output(value_index); });
+        circuit
+    }
+
+    fn pseudo_random_inputs(input_count: usize, length: usize) -> Vec<Vec<Value>> {
+        let mut seed: usize = 12345;
+        (0..length)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345) % (1 << 31);
+                to_values(seed >> 16, input_count)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn moore_component() {
+        let machine = sequence_detector();
+        let sequence: Vec<_> = [1, 1, 0, 1, 1, 1, 0, 0].iter().map(|&bit| to_values(bit, 1)).collect();
+        let found: Vec<_> = run(component_circuit(&machine), &sequence).into_iter().map(|outputs| outputs[0]).collect();
+
+        // found is On for the clock cycle after the third and seventh input
+        let expected: Vec<_> = [0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 0].iter().map(|&bit| to_values(bit, 1)[0]).collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn mealy_component() {
+        let machine = arbiter();
+        let on_on = vec![Value::On, Value::On];
+        let off_on = vec![Value::Off, Value::On];
+        let off_off = vec![Value::Off, Value::Off];
+
+        assert_eq!(machine.output_values(0, &on_on), [Value::On, Value::Off]);
+        assert_eq!(machine.next_state(0, &on_on), 1);
+        assert_eq!(machine.next_state(0, &off_on), 2);
+        assert_eq!(machine.output_values(2, &off_off), [Value::Off, Value::Off]);
+        assert_eq!(machine.next_state(2, &off_off), 0);
+        assert_eq!(machine.next_state(1, &on_on), 1);
+
+        let outputs = run(component_circuit(&machine), &[off_on.clone(), off_on, off_off]);
+        assert_eq!(outputs, [[Value::Off, Value::On], [Value::Off, Value::On], [Value::Off, Value::On], [Value::Off, Value::On], [Value::Off, Value::Off], [Value::Off, Value::Off]]);
+    }
+
+    #[test]
+    fn synthesis_matches_component() {
+        for machine in [sequence_detector(), arbiter()] {
+            let sequence = pseudo_random_inputs(machine.inputs().len(), 64);
+            let expected = run(component_circuit(&machine), &sequence);
+
+            for encoding in [StateEncoding::Binary, StateEncoding::OneHot, StateEncoding::Gray] {
+                let circuit = machine.synthesize(encoding);
+                let flip_flop_count = circuit.all_components().iter().filter(|component| matches!(component.function(), Function::FlipFlopD { .. })).count();
+                assert_eq!(flip_flop_count, encoding.width(machine.state_count()));
+
+                assert_eq!(run(circuit, &sequence), expected, "{encoding} encoding of a {} machine", machine.kind());
+            }
+        }
+    }
+
+    #[test]
+    fn clock_edges() {
+        let mut toggle: StateMachine = serde_json::from_str(r#"{
+            "kind": "Moore",
+            "inputs": ["toggle"],
+            "outputs": ["on"],
+            "states": [{ "name": "Off" }, { "name": "On", "outputs": "1" }],
+            "transitions": [{ "from": "Off", "condition": "1", "to": "On" }, { "from": "On", "condition": "1", "to": "Off" }],
+            "edge": "Falling"
+        }"#).unwrap();
+        assert_eq!(toggle.edge(), ClockEdge::Falling);
+
+        let sequence = vec![vec![Value::On]; 2];
+        let on = |bits: &[u8]| -> Vec<Vec<Value>> { bits.iter().map(|&bit| to_values(bit as usize, 1)).collect() };
+        assert_eq!(run(component_circuit(&toggle), &sequence), on(&[0, 0, 1, 1]));
+
+        toggle.set_edge(ClockEdge::Dual);
+        assert_eq!(run(component_circuit(&toggle), &sequence), on(&[0, 1, 0, 1]));
+
+        for edge in [ClockEdge::Falling, ClockEdge::Dual] {
+            for mut machine in [sequence_detector(), arbiter()] {
+                machine.set_edge(edge);
+                let sequence = pseudo_random_inputs(machine.inputs().len(), 64);
+                let expected = run(component_circuit(&machine), &sequence);
+
+                for encoding in [StateEncoding::Binary, StateEncoding::OneHot, StateEncoding::Gray] {
+                    assert_eq!(run(machine.synthesize(encoding), &sequence), expected, "{encoding} encoding of a {} machine on {edge} edges", machine.kind());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn serialization() {
+        let machine = arbiter();
+        let serialized = serde_json::to_string(&machine).unwrap();
+        assert_eq!(serde_json::from_str::<StateMachine>(&serialized).unwrap(), machine);
+
+        let mut falling = sequence_detector();
+        falling.set_edge(ClockEdge::Falling);
+        let serialized = serde_json::to_string(&falling).unwrap();
+        assert_eq!(serde_json::from_str::<StateMachine>(&serialized).unwrap(), falling);
+
+        let toggle: StateMachine = serde_json::from_str(r#"{
+            "kind": "Moore",
+            "inputs": ["toggle"],
+            "outputs": ["on"],
+            "states": [{ "name": "Off" }, { "name": "On", "outputs": "1" }],
+            "transitions": [{ "from": "Off", "condition": "1", "to": "On" }, { "from": "On", "condition": "1", "to": "Off" }]
+        }"#).unwrap();
+        assert_eq!(toggle.state_index("On"), Some(1));
+        assert_eq!(toggle.next_state(0, &[Value::On]), 1);
+        assert_eq!(toggle.output_values(1, &[Value::Off]), [Value::On]);
+        assert_eq!(toggle.edge(), ClockEdge::Rising);
+
+        let unknown = r#"{ "kind": "Moore", "inputs": [], "outputs": [], "states": [{ "name": "A" }], "transitions": [{ "from": "A", "to": "B" }] }"#;
+        assert!(serde_json::from_str::<StateMachine>(unknown).is_err());
+
+        let mut machine = StateMachine::new(MachineKind::Moore, vec!["a".into()], vec![]);
+        machine.add_state("A", "").unwrap();
+        assert_eq!(machine.add_state("A", ""), Err(StateMachineError::DuplicateState("A".into())));
+        assert_eq!(machine.add_transition("A", "10", "A", ""), Err(StateMachineError::InvalidBits { bits: "10".into(), expected: 1 }));
+    }
+
+    #[test]
+    fn machine_without_states() {
+        let empty = StateMachine::new(MachineKind::Moore, vec!["in".into()], vec!["out".into()]);
+        let function = Function::StateMachine(empty);
+        assert_eq!(function.validate(), Err(FunctionError::InvalidStateMachine(StateMachineError::NoStates)));
+
+        let mut circuit = Circuit::new();
+        let (_, input) = circuit.add_input();
+        let (_, clock) = circuit.add_input();
+        assert_eq!(circuit.add_component(function, vec![input, clock]), Err(CircuitError::InvalidFunction(FunctionError::InvalidStateMachine(StateMachineError::NoStates))));
+
+        let empty = r#"{ "kind": "Moore", "inputs": [], "outputs": [], "states": [] }"#;
+        assert!(serde_json::from_str::<StateMachine>(empty).is_err());
+    }
+}
@@ -0,0 +1,507 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Value, function::{to_number, to_values}};
+
+/// Largest number of inputs of a truth table, which has `2^input_count` rows.
+pub const MAX_INPUT_COUNT: usize = 20;
+
+/// Output table of a combinational block with `input_count` inputs and
+/// `output_count` outputs, used by `Function::TruthTable`.
+///
+/// Row `r` holds the outputs for the inputs whose values read as the number
+/// `r` with input 0 as the least significant bit. In JSON the table is either
+/// a bit string of all rows in order, each with its outputs in order, or a
+/// list of rows like `"1-0 01"` in the style of PLA files. There the inputs in
+/// order, where `-` matches both values, are followed by the outputs that are
+/// `On` for all matching inputs. Outputs of inputs without a row are `Off`.
+/// Tables are always saved as bit strings.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "TruthTableData", into = "TruthTableData")]
+pub struct TruthTable {
+    input_count: usize,
+    output_count: usize,
+    outputs: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TruthTableData {
+    input_count: usize,
+    output_count: usize,
+    table: TableData,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TableData {
+    Bits(String),
+    Rows(Vec<String>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TruthTableError {
+    TooManyInputs(usize),
+    Length { expected: usize, actual: usize },
+    InvalidRow { row: usize, message: String },
+    /// A PLA file that `TruthTable::from_pla` cannot read, where `line` starts at 1.
+    Pla { line: usize, message: String },
+    /// A BLIF file that `TruthTable::from_blif` cannot read, where `line` starts at 1.
+    Blif { line: usize, message: String },
+}
+
+/// A `.names` block of a BLIF file. Its output is `value` for the inputs that
+/// match one of the rows and the inverse otherwise, or `Off` without rows.
+struct BlifCover {
+    inputs: Vec<String>,
+    output: String,
+    rows: Vec<Vec<Option<Value>>>,
+    value: Option<Value>,
+}
+
+impl TruthTable {
+    /// Creates a table whose outputs are all `Off`.
+    pub fn new(input_count: usize, output_count: usize) -> Result<Self, TruthTableError> {
+        if input_count > MAX_INPUT_COUNT {
+            return Err(TruthTableError::TooManyInputs(input_count));
+        }
+
+        Ok(TruthTable { input_count, output_count, outputs: vec![Value::Off; (1 << input_count) * output_count] })
+    }
+
+    /// Creates a table from the outputs `function` calculates for every row.
+    pub fn from_fn(input_count: usize, output_count: usize, function: impl Fn(&[Value]) -> Vec<Value>) -> Result<Self, TruthTableError> {
+        let mut table = Self::new(input_count, output_count)?;
+
+        for row in 0..table.row_count() {
+            let output_values = function(&to_values(row, input_count));
+            table.set_row(row, &output_values);
+        }
+
+        Ok(table)
+    }
+
+    /// Parses the bit string of all rows, ignoring whitespace and underscores.
+    pub fn from_bits(input_count: usize, output_count: usize, bits: &str) -> Result<Self, TruthTableError> {
+        let mut table = Self::new(input_count, output_count)?;
+
+        let outputs = bits.chars()
+            .filter(|&ch| !ch.is_whitespace() && ch != '_')
+            .enumerate()
+            .map(|(i, ch)| parse_bit(ch).ok_or_else(|| TruthTableError::InvalidRow {
+                row: i / output_count.max(1),
+                message: format!("'{ch}' is not a bit"),
+            }))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if outputs.len() != table.outputs.len() {
+            return Err(TruthTableError::Length { expected: table.outputs.len(), actual: outputs.len() });
+        }
+
+        table.outputs = outputs;
+        Ok(table)
+    }
+
+    /// Parses a list of rows with don't care inputs, see `TruthTable`.
+    pub fn from_rows<S: AsRef<str>>(input_count: usize, output_count: usize, rows: &[S]) -> Result<Self, TruthTableError> {
+        let mut table = Self::new(input_count, output_count)?;
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let invalid_row = |message: String| TruthTableError::InvalidRow { row: row_index, message };
+
+            let [inputs, outputs] = row.as_ref().split_whitespace().collect::<Vec<_>>()[..] else {
+                return Err(invalid_row("expected inputs and outputs separated by whitespace".into()));
+            };
+
+            if inputs.len() != input_count || outputs.len() != output_count {
+                return Err(invalid_row(format!("expected {input_count} inputs and {output_count} outputs")));
+            }
+
+            let pattern = inputs.chars()
+                .map(|ch| match ch {
+                    '-' => Ok(None),
+                    _ => parse_bit(ch).map(Some).ok_or_else(|| invalid_row(format!("'{ch}' is not an input bit"))),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let output_values = outputs.chars()
+                .map(|ch| parse_bit(ch).ok_or_else(|| invalid_row(format!("'{ch}' is not an output bit"))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            for row in 0..table.row_count() {
+                let matches = to_values(row, input_count).iter().zip(&pattern)
+                    .all(|(value, expected)| expected.is_none_or(|expected| expected == *value));
+
+                if matches {
+                    let start = row * output_count;
+                    for (output, &value) in table.outputs[start..start + output_count].iter_mut().zip(&output_values) {
+                        *output = *output | value;
+                    }
+                }
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Parses a PLA file of type `f`, whose rows are read like `from_rows`
+    /// after outputs written as `-` or `~` are turned into `0`. The keywords
+    /// `.p`, `.ilb` and `.ob` are ignored and everything after `.e` is skipped.
+    pub fn from_pla(text: &str) -> Result<Self, TruthTableError> {
+        let mut input_count = None;
+        let mut output_count = None;
+        let mut rows = Vec::new();
+        let mut row_lines = Vec::new();
+        let mut line_count = 0;
+
+        for (line_index, line) in text.lines().enumerate() {
+            let pla_error = |message: String| TruthTableError::Pla { line: line_index + 1, message };
+            let parse_count = |count: Option<&str>| count.and_then(|count| count.parse::<usize>().ok()).ok_or_else(|| pla_error("expected a count".into()));
+            let mut words = line.split('#').next().unwrap_or_default().split_whitespace();
+            line_count = line_index + 1;
+
+            match words.next() {
+                None => {},
+                Some(".i") => input_count = Some(parse_count(words.next())?),
+                Some(".o") => output_count = Some(parse_count(words.next())?),
+                Some(".type") if words.next() != Some("f") => return Err(pla_error("only type f is supported".into())),
+                Some(".p" | ".ilb" | ".ob" | ".type") => {},
+                Some(".e" | ".end") => break,
+                Some(keyword) if keyword.starts_with('.') => return Err(pla_error(format!("{keyword} is not supported"))),
+                Some(inputs) => {
+                    if input_count.is_none() || output_count.is_none() {
+                        return Err(pla_error("rows have to follow .i and .o".into()));
+                    }
+
+                    let outputs: String = words.collect::<String>().chars()
+                        .map(|ch| if ch == '-' || ch == '~' { '0' } else { ch })
+                        .collect();
+                    rows.push(format!("{inputs} {outputs}"));
+                    row_lines.push(line_index + 1);
+                },
+            }
+        }
+
+        let (Some(input_count), Some(output_count)) = (input_count, output_count) else {
+            return Err(TruthTableError::Pla { line: line_count, message: "missing .i or .o".into() });
+        };
+
+        TruthTable::from_rows(input_count, output_count, &rows).map_err(|error| match error {
+            TruthTableError::InvalidRow { row, message } => TruthTableError::Pla { line: row_lines[row], message },
+            error => error,
+        })
+    }
+
+    /// Parses a combinational BLIF model made of `.names` blocks, whose
+    /// inputs are the model inputs or outputs of other blocks. Rows are read
+    /// like `from_rows` with a single output, which has to be the same in all
+    /// rows of a block. Lines ending in `\` continue on the next line,
+    /// `.model` is ignored and everything after `.end` is skipped.
+    pub fn from_blif(text: &str) -> Result<Self, TruthTableError> {
+        let mut inputs: Vec<String> = Vec::new();
+        let mut outputs: Vec<String> = Vec::new();
+        let mut covers: Vec<BlifCover> = Vec::new();
+        let mut cover_lines = Vec::new();
+        let mut line = String::new();
+        let mut line_number = 0;
+
+        for (line_index, text_line) in text.lines().enumerate() {
+            let content = text_line.split('#').next().unwrap_or_default();
+            if line.is_empty() {
+                line_number = line_index + 1;
+            }
+
+            if let Some(content) = content.trim_end().strip_suffix('\\') {
+                line.push_str(content);
+                line.push(' ');
+                continue;
+            }
+
+            line.push_str(content);
+            let blif_error = |message: String| TruthTableError::Blif { line: line_number, message };
+            let mut words = line.split_whitespace();
+
+            match words.next() {
+                None | Some(".model") => {},
+                Some(".inputs") => inputs.extend(words.map(str::to_owned)),
+                Some(".outputs") => outputs.extend(words.map(str::to_owned)),
+                Some(".names") => {
+                    let mut signals: Vec<String> = words.map(str::to_owned).collect();
+                    let Some(output) = signals.pop() else {
+                        return Err(blif_error(".names needs an output".into()));
+                    };
+
+                    if inputs.contains(&output) || covers.iter().any(|cover| cover.output == output) {
+                        return Err(blif_error(format!("{output} is defined twice")));
+                    }
+
+                    covers.push(BlifCover { inputs: signals, output, rows: vec![], value: None });
+                    cover_lines.push(line_number);
+                },
+                Some(".end") => break,
+                Some(keyword) if keyword.starts_with('.') => return Err(blif_error(format!("{keyword} is not supported"))),
+                Some(first) => {
+                    let Some(cover) = covers.last_mut() else {
+                        return Err(blif_error("rows have to follow .names".into()));
+                    };
+
+                    let (pattern, value) = if cover.inputs.is_empty() { ("", Some(first)) } else { (first, words.next()) };
+                    if pattern.len() != cover.inputs.len() || words.next().is_some() {
+                        return Err(blif_error(format!("expected {} inputs and an output", cover.inputs.len())));
+                    }
+
+                    let pattern = pattern.chars()
+                        .map(|ch| match ch {
+                            '-' => Ok(None),
+                            _ => parse_bit(ch).map(Some).ok_or_else(|| blif_error(format!("'{ch}' is not an input bit"))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let value = match value {
+                        Some("0") => Value::Off,
+                        Some("1") => Value::On,
+                        _ => return Err(blif_error("expected 0 or 1 as output".into())),
+                    };
+
+                    if cover.value.is_some_and(|cover_value| cover_value != value) {
+                        return Err(blif_error(format!("the rows of {} have different outputs", cover.output)));
+                    }
+
+                    cover.value = Some(value);
+                    cover.rows.push(pattern);
+                },
+            }
+
+            line.clear();
+        }
+
+        // orders the blocks so that every block follows the blocks of its inputs
+        let mut known: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        let mut ordered: Vec<&BlifCover> = Vec::new();
+        let mut remaining: Vec<usize> = (0..covers.len()).collect();
+
+        while !remaining.is_empty() {
+            let (ready, blocked): (Vec<usize>, Vec<usize>) = remaining.into_iter()
+                .partition(|&cover| covers[cover].inputs.iter().all(|input| known.contains(&input.as_str())));
+
+            if ready.is_empty() {
+                let cover = &covers[blocked[0]];
+                let input = cover.inputs.iter().find(|input| !known.contains(&input.as_str())).unwrap();
+                let message = if covers.iter().any(|other| &other.output == input) {
+                    format!("{} depends on itself", cover.output)
+                } else {
+                    format!("{input} is never defined")
+                };
+                return Err(TruthTableError::Blif { line: cover_lines[blocked[0]], message });
+            }
+
+            known.extend(ready.iter().map(|&cover| covers[cover].output.as_str()));
+            ordered.extend(ready.iter().map(|&cover| &covers[cover]));
+            remaining = blocked;
+        }
+
+        if let Some(output) = outputs.iter().find(|output| !known.contains(&output.as_str())) {
+            return Err(TruthTableError::Blif { line: line_number, message: format!("output {output} is never defined") });
+        }
+
+        TruthTable::from_fn(inputs.len(), outputs.len(), |input_values| {
+            let mut values: HashMap<&str, Value> = inputs.iter().map(String::as_str).zip(input_values.iter().copied()).collect();
+
+            for cover in &ordered {
+                let matches = cover.rows.iter().any(|row| row.iter().zip(&cover.inputs)
+                    .all(|(expected, input)| expected.is_none_or(|expected| expected == values[input.as_str()])));
+                let value = match cover.value {
+                    Some(value) if matches => value,
+                    Some(value) => !value,
+                    None => Value::Off,
+                };
+                values.insert(&cover.output, value);
+            }
+
+            outputs.iter().map(|output| values[output.as_str()]).collect()
+        })
+    }
+
+    pub fn to_bits(&self) -> String {
+        self.outputs.iter().map(|&value| if value == Value::On { '1' } else { '0' }).collect()
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.input_count
+    }
+
+    pub fn output_count(&self) -> usize {
+        self.output_count
+    }
+
+    pub fn row_count(&self) -> usize {
+        1 << self.input_count
+    }
+
+    pub fn row(&self, row: usize) -> &[Value] {
+        &self.outputs[row * self.output_count..(row + 1) * self.output_count]
+    }
+
+    pub fn set_row(&mut self, row: usize, output_values: &[Value]) {
+        assert_eq!(output_values.len(), self.output_count, "a row needs a value for every output");
+        self.outputs[row * self.output_count..(row + 1) * self.output_count].copy_from_slice(output_values);
+    }
+
+    pub fn evaluate(&self, input_values: &[Value]) -> Vec<Value> {
+        self.row(to_number(input_values)).to_vec()
+    }
+}
+
+fn parse_bit(ch: char) -> Option<Value> {
+    match ch {
+        '0' => Some(Value::Off),
+        '1' => Some(Value::On),
+        _ => None,
+    }
+}
+
+impl TryFrom<TruthTableData> for TruthTable {
+    type Error = TruthTableError;
+
+    fn try_from(data: TruthTableData) -> Result<Self, Self::Error> {
+        match data.table {
+            TableData::Bits(bits) => TruthTable::from_bits(data.input_count, data.output_count, &bits),
+            TableData::Rows(rows) => TruthTable::from_rows(data.input_count, data.output_count, &rows),
+        }
+    }
+}
+
+impl From<TruthTable> for TruthTableData {
+    fn from(table: TruthTable) -> Self {
+        TruthTableData { input_count: table.input_count, output_count: table.output_count, table: TableData::Bits(table.to_bits()) }
+    }
+}
+
+impl Display for TruthTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TruthTableError::TooManyInputs(count) => write!(f, "{count} inputs are more than the supported {MAX_INPUT_COUNT}"),
+            TruthTableError::Length { expected, actual } => write!(f, "expected {expected} output bits but got {actual}"),
+            TruthTableError::InvalidRow { row, message } => write!(f, "invalid row {row}: {message}"),
+            TruthTableError::Pla { line, message } => write!(f, "invalid PLA file in line {line}: {message}"),
+            TruthTableError::Blif { line, message } => write!(f, "invalid BLIF file in line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for TruthTableError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Circuit, function::Function, simulator::Simulator};
+
+    use super::*;
+
+    #[test]
+    fn bit_string_and_rows() {
+        let xor = TruthTable::from_bits(2, 1, "0110").unwrap();
+        assert_eq!(xor.evaluate(&[Value::On, Value::Off]), [Value::On]);
+        assert_eq!(xor.evaluate(&[Value::On, Value::On]), [Value::Off]);
+
+        // half adder with the outputs sum and carry
+        let half_adder = TruthTable::from_bits(2, 2, "00 10 10 01").unwrap();
+        assert_eq!(half_adder.row(3), [Value::Off, Value::On]);
+        assert_eq!(half_adder, TruthTable::from_rows(2, 2, &["10 10", "01 10", "11 01"]).unwrap());
+        assert_eq!(half_adder, TruthTable::from_fn(2, 2, |values| vec![values[0] ^ values[1], values[0] & values[1]]).unwrap());
+
+        // don't care inputs and or'ed outputs of overlapping rows
+        let table = TruthTable::from_rows(3, 2, &["1-- 10", "-1- 01"]).unwrap();
+        assert_eq!(table.to_bits(), "0010011100100111");
+        assert_eq!(table.row(0), [Value::Off, Value::Off]);
+        assert_eq!(table.row(1), [Value::On, Value::Off]);
+        assert_eq!(table.row(2), [Value::Off, Value::On]);
+        assert_eq!(table.row(3), [Value::On, Value::On]);
+        assert_eq!(table.row(4), [Value::Off, Value::Off]);
+
+        assert_eq!(TruthTable::from_bits(2, 1, "011"), Err(TruthTableError::Length { expected: 4, actual: 3 }));
+        assert!(matches!(TruthTable::from_bits(2, 1, "01x1"), Err(TruthTableError::InvalidRow { row: 2, .. })));
+        assert!(matches!(TruthTable::from_rows(2, 1, &["1 1"]), Err(TruthTableError::InvalidRow { row: 0, .. })));
+        assert_eq!(TruthTable::new(MAX_INPUT_COUNT + 1, 1), Err(TruthTableError::TooManyInputs(MAX_INPUT_COUNT + 1)));
+    }
+
+    #[test]
+    fn pla() {
+        let text = "# full adder\n.i 3\n.o 2\n.ilb a b cin\n.ob sum cout\n.p 7\n100 10\n010 10\n001 10\n111 11\n11- -1\n1-1 ~1\n-11 01\n.e\nignored\n";
+        let full_adder = TruthTable::from_pla(text).unwrap();
+        let expected = TruthTable::from_fn(3, 2, |values| {
+            let sum = values.iter().filter(|&&value| value == Value::On).count();
+            to_values(sum, 2)
+        }).unwrap();
+        assert_eq!(full_adder, expected);
+
+        assert_eq!(TruthTable::from_pla(".i 2\n.o 1\n.type fr\n"), Err(TruthTableError::Pla { line: 3, message: "only type f is supported".into() }));
+        assert_eq!(TruthTable::from_pla("11 1\n"), Err(TruthTableError::Pla { line: 1, message: "rows have to follow .i and .o".into() }));
+        assert!(matches!(TruthTable::from_pla(".i 2\n.o 1\n1 1\n"), Err(TruthTableError::Pla { line: 3, .. })));
+        assert!(matches!(TruthTable::from_pla(".i 2\n"), Err(TruthTableError::Pla { line: 1, .. })));
+        assert!(matches!(TruthTable::from_pla(".i x\n"), Err(TruthTableError::Pla { line: 1, .. })));
+    }
+
+    #[test]
+    fn blif() {
+        let text = "# full adder\n.model full_adder\n.inputs a b \\\n  cin\n.outputs sum cout one\n.names a b half # xor\n10 1\n01 1\n.names half cin sum\n10 1\n01 1\n.names a b cin cout\n00- 0\n0-0 0\n-00 0\n.names one\n1\n.end\nignored\n";
+        let full_adder = TruthTable::from_blif(text).unwrap();
+        let expected = TruthTable::from_fn(3, 3, |values| {
+            let sum = values.iter().filter(|&&value| value == Value::On).count();
+            let mut outputs = to_values(sum, 2);
+            outputs.push(Value::On);
+            outputs
+        }).unwrap();
+        assert_eq!(full_adder, expected);
+
+        let blif_error = |line, message: &str| Err(TruthTableError::Blif { line, message: message.into() });
+        assert_eq!(TruthTable::from_blif(".inputs a\n.outputs y\n.latch a y\n"), blif_error(3, ".latch is not supported"));
+        assert_eq!(TruthTable::from_blif("1 1\n"), blif_error(1, "rows have to follow .names"));
+        assert_eq!(TruthTable::from_blif(".inputs a\n.names a\n"), blif_error(2, "a is defined twice"));
+        assert_eq!(TruthTable::from_blif(".inputs a\n.outputs y\n.names a y\n1 1\n0 0\n"), blif_error(5, "the rows of y have different outputs"));
+        assert_eq!(TruthTable::from_blif(".inputs a\n.outputs y\n.names b y\n1 1\n"), blif_error(3, "b is never defined"));
+        assert_eq!(TruthTable::from_blif(".inputs a\n.outputs y\n.names a z y\n11 1\n.names y z\n1 1\n"), blif_error(3, "y depends on itself"));
+        assert_eq!(TruthTable::from_blif(".inputs a\n.outputs y\n"), blif_error(2, "output y is never defined"));
+        assert!(matches!(TruthTable::from_blif(".inputs a\n.outputs y\n.names a y\n1- 1\n"), Err(TruthTableError::Blif { line: 4, .. })));
+        assert!(matches!(TruthTable::from_blif(".inputs a\n.outputs y\n.names a y\n1 x\n"), Err(TruthTableError::Blif { line: 4, .. })));
+    }
+
+    #[test]
+    fn serialization() {
+        let bits: TruthTable = serde_json::from_str(r#"{"input_count":2,"output_count":1,"table":"0001"}"#).unwrap();
+        let rows: TruthTable = serde_json::from_str(r#"{"input_count":2,"output_count":1,"table":["11 1"]}"#).unwrap();
+        assert_eq!(bits, rows);
+        assert_eq!(serde_json::to_string(&rows).unwrap(), r#"{"input_count":2,"output_count":1,"table":"0001"}"#);
+
+        assert!(serde_json::from_str::<TruthTable>(r#"{"input_count":2,"output_count":1,"table":"01"}"#).is_err());
+    }
+
+    #[test]
+    fn truth_table_component() {
+        let mut circuit = Circuit::new();
+        let (_, a) = circuit.add_input();
+        let (_, b) = circuit.add_input();
+        let (_, c) = circuit.add_input();
+        let majority = TruthTable::from_rows(3, 1, &["11- 1", "1-1 1", "-11 1"]).unwrap();
+        let (_, outputs) = circuit.add_component(Function::TruthTable(majority), vec![a, b, c]).unwrap();
+        circuit.add_output(outputs[0]);
+
+        let mut simulator = Simulator::new(circuit).unwrap();
+
+        for row in 0..8 {
+            let input_values = to_values(row, 3);
+            for (input_index, &value) in input_values.iter().enumerate() {
+                simulator.set_input(input_index, value);
+            }
+
+            assert!(simulator.simulate().is_ok());
+            let expected = if row.count_ones() >= 2 { Value::On } else { Value::Off };
+            assert_eq!(simulator.get_output_value(0), expected);
+        }
+
+        // editing the table updates the output right away
+        let mut table = TruthTable::from_bits(3, 1, "11111111").unwrap();
+        table.set_row(7, &[Value::Off]);
+        assert!(simulator.set_truth_table(0, table));
+        assert!(simulator.simulate().is_ok());
+        assert_eq!(simulator.get_output_value(0), Value::Off);
+        assert!(!simulator.set_truth_table(0, TruthTable::new(2, 1).unwrap()));
+    }
+}